@@ -0,0 +1,249 @@
+//! Functions used for converting objects to and from apgcodes.
+//!
+//! An [apgcode](http://conwaylife.com/wiki/Apgcode) identifies an object regardless of its
+//! phase, rotation and reflection, for example `xs4_33` for the block or `xq4_153` for the
+//! glider. The prefix gives the kind of object and its population or period, followed by
+//! the object itself in extended Wechsler format.
+
+use std::error;
+use std::fmt;
+
+use period::{ simulate, Kind };
+use grid::{ Grid, Cell };
+
+/// The characters used for each column of a five-row strip, and for runs of empty columns
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// The number of rows in each strip
+const STRIP: usize = 5;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    InvalidPrefix,
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ParseError::*;
+        match *self {
+            InvalidPrefix        => write!(f, "Apgcode did not begin with xs, xp or xq"),
+            InvalidCharacter(c)  => write!(f, "Invalid extended Wechsler character: {}", c),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+pub type ParseResult = Result<Grid, ParseError>;
+
+/// Returns the apgcode of `grid`, which must hold a single still life, oscillator or
+/// spaceship under the standard rules.
+///
+/// Returns `None` if the object is unstable.
+pub fn encode(grid: &Grid) -> Option<String> {
+    match simulate(grid) {
+        (Kind::Unstable, _) => None,
+        (kind, phases)      => Some(encode_phases(kind, &phases)),
+    }
+}
+
+/// Returns the apgcode of an object of `kind` with the given `phases`
+pub(crate) fn encode_phases(kind: Kind, phases: &[Grid]) -> String {
+    let prefix = match kind {
+        Kind::Still                    => format!("xs{}", phases[0].population()),
+        Kind::Oscillator { period }    => format!("xp{}", period),
+        Kind::Spaceship { period, .. } => format!("xq{}", period),
+        Kind::Unstable                 => unreachable!("Unstable objects have no apgcode"),
+    };
+    // Use the shortest encoding of any phase and orientation, then the first alphabetically
+    let body = phases
+        .iter()
+        .flat_map(Grid::symmetries)
+        .map(|g| encode_wechsler(&g))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default();
+    format!("{}_{}", prefix, body)
+}
+
+/// Returns the object described by an apgcode such as `xs4_33`
+pub fn decode(code: &str) -> ParseResult {
+    let mut parts = code.splitn(2, '_');
+    match (parts.next(), parts.next()) {
+        (Some(prefix), Some(body)) if is_prefix(prefix) => decode_wechsler(body),
+        _                                               => Err(ParseError::InvalidPrefix),
+    }
+}
+
+/// Returns true if `prefix` is `xs`, `xp` or `xq` followed by a number
+fn is_prefix(prefix: &str) -> bool {
+    let number = prefix.strip_prefix("xs")
+        .or_else(|| prefix.strip_prefix("xp"))
+        .or_else(|| prefix.strip_prefix("xq"));
+    match number {
+        Some(n) => !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()),
+        None    => false,
+    }
+}
+
+/// Encodes the live cells of `grid`, cropped to their bounding box, in extended
+/// Wechsler format
+pub fn encode_wechsler(grid: &Grid) -> String {
+    let (x0, y0, w, h) = match grid.live_bounds() {
+        Some(bounds) => bounds,
+        None         => return String::new(),
+    };
+    let mut out = String::new();
+
+    for strip in 0..h.div_ceil(STRIP) {
+        if strip > 0 {
+            out.push('z');
+        }
+        let mut zeros = 0;
+        for x in 0..w {
+            let column = (0..STRIP)
+                .map(|row| strip * STRIP + row)
+                .filter(|&y| y < h && grid.cell_at(x0 + x, y0 + y).is_live())
+                .fold(0, |bits, y| bits | (1 << (y % STRIP)));
+            if column == 0 {
+                zeros += 1;
+                continue;
+            }
+            push_zeros(&mut out, zeros);
+            zeros = 0;
+            out.push(DIGITS[column] as char);
+        }
+    }
+
+    out
+}
+
+/// Appends a run of `zeros` empty columns
+fn push_zeros(out: &mut String, mut zeros: usize) {
+    while zeros >= 40 {
+        out.push_str("yz");
+        zeros -= 39;
+    }
+    match zeros {
+        0 => {},
+        1 => out.push('0'),
+        2 => out.push('w'),
+        3 => out.push('x'),
+        n => {
+            out.push('y');
+            out.push(DIGITS[n - 4] as char);
+        },
+    }
+}
+
+/// Decodes a pattern in extended Wechsler format
+pub fn decode_wechsler(s: &str) -> ParseResult {
+    let mut live = Vec::new();
+    let (mut x, mut strip) = (0, 0);
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            'w' => x += 2,
+            'x' => x += 3,
+            'y' => {
+                let n = chars.next().ok_or(ParseError::InvalidCharacter(c))?;
+                x += 4 + digit(n, 36)?;
+            },
+            'z' => {
+                x = 0;
+                strip += 1;
+            },
+            c => {
+                let column = digit(c, 32)?;
+                for row in (0..STRIP).filter(|row| column & (1 << row) != 0) {
+                    live.push((x, strip * STRIP + row));
+                }
+                x += 1;
+            },
+        }
+    }
+
+    let width = live.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let height = live.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+    let mut grid = Grid::create_dead(width, height);
+    for (x, y) in live {
+        grid.set_cell(x, y, Cell::Live);
+    }
+    Ok(grid)
+}
+
+/// Returns the value of the character `c` if it is one of the first `count` digits
+fn digit(c: char, count: usize) -> Result<usize, ParseError> {
+    DIGITS[..count]
+        .iter()
+        .position(|&d| d as char == c)
+        .ok_or(ParseError::InvalidCharacter(c))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ encode, decode, encode_wechsler, decode_wechsler, ParseError };
+    use grid::{ Grid, Cell };
+    use grid::tests as grid_test;
+    use grid::tests::make_grid;
+    use grid::tests::{ make_pulsar_grid, make_lwss_grid };
+
+    #[test]
+    fn can_encode_well_known_objects() {
+        let cases = vec![
+            (make_grid(&["OO", "OO"]), "xs4_33"),
+            (make_grid(&[".OO.", "O..O", ".OO."]), "xs6_696"),
+            (make_grid(&["OO.", "O.O", ".O."]), "xs5_253"),
+            (make_grid(&[".O.", "O.O", ".O."]), "xs4_252"),
+            (make_grid(&[".OO.", "O..O", "O..O", ".OO."]), "xs8_6996"),
+            (make_grid(&["OOO"]), "xp2_7"),
+            (make_grid(&[".OOO", "OOO."]), "xp2_7e"),
+            (make_grid(&["OO..", "OO..", "..OO", "..OO"]), "xp2_318c"),
+            (make_pulsar_grid(), "xp3_co9nas0san9oczgoldlo0oldlogz1047210127401"),
+            (grid_test::make_glider_grid(), "xq4_153"),
+            (make_lwss_grid(), "xq4_6frc"),
+        ];
+
+        for (grid, code) in cases {
+            assert_eq!(encode(&grid).as_ref().map(|s| &s[..]), Some(code));
+        }
+    }
+
+    #[test]
+    fn unstable_objects_have_no_code() {
+        assert_eq!(encode(&make_grid(&["OO", "O."])), None);
+    }
+
+    #[test]
+    fn can_decode_well_known_objects() {
+        assert_eq!(decode("xs4_33"), Ok(make_grid(&["OO", "OO"])));
+        assert_eq!(decode("xq4_153"), Ok(make_grid(&["OOO", "..O", ".O."])));
+        assert_eq!(decode("xp2_7"), Ok(make_grid(&["O", "O", "O"])));
+
+        let pulsar = decode("xp3_co9nas0san9oczgoldlo0oldlogz1047210127401").unwrap();
+        assert_eq!(encode(&pulsar).unwrap(), "xp3_co9nas0san9oczgoldlo0oldlogz1047210127401");
+    }
+
+    #[test]
+    fn can_round_trip_wechsler() {
+        let mut grid = Grid::create_dead(100, 12);
+        grid.set_cell(0, 0, Cell::Live);
+        grid.set_cell(2, 4, Cell::Live);
+        grid.set_cell(6, 6, Cell::Live);
+        grid.set_cell(99, 11, Cell::Live);
+
+        let s = encode_wechsler(&grid);
+        assert_eq!(s, "10gzy22zyzyzyh2");
+        assert_eq!(decode_wechsler(&s), Ok(grid));
+    }
+
+    #[test]
+    fn invalid_codes_fail() {
+        assert_eq!(decode("33"), Err(ParseError::InvalidPrefix));
+        assert_eq!(decode("xs_33"), Err(ParseError::InvalidPrefix));
+        assert_eq!(decode("xs4_3!"), Err(ParseError::InvalidCharacter('!')));
+        assert_eq!(decode("xs4_3y"), Err(ParseError::InvalidCharacter('y')));
+    }
+}
@@ -0,0 +1,44 @@
+//! Utilities for processing the rows of a grid in parallel horizontal bands.
+
+use std::thread;
+
+/// Splits `out`, which holds `rows` rows of `row_len` items, into at most `threads`
+/// bands of consecutive rows and calls `f` with the index of the first row in each band
+/// and the band itself. When `threads` is greater than one the bands are processed on
+/// separate threads.
+pub fn for_each_band<T, F>(out: &mut [T], row_len: usize, rows: usize, threads: usize, f: F)
+    where T: Send, F: Fn(usize, &mut [T]) + Sync
+{
+    if threads <= 1 || rows <= 1 || row_len == 0 {
+        f(0, out);
+        return;
+    }
+
+    let band_rows = rows.div_ceil(threads);
+    let f = &f;
+    thread::scope(|scope| {
+        for (band, chunk) in out.chunks_mut(band_rows * row_len).enumerate() {
+            scope.spawn(move || f(band * band_rows, chunk));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::for_each_band;
+
+    #[test]
+    fn visits_every_row_once() {
+        for threads in 0..12 {
+            let mut out = vec![0; 7 * 3];
+            for_each_band(&mut out, 3, 7, threads, |first, band| {
+                for (i, item) in band.iter_mut().enumerate() {
+                    *item += first * 3 + i + 1;
+                }
+            });
+            let expected: Vec<_> = (1..22).collect();
+            assert_eq!(out, expected, "{} threads", threads);
+        }
+    }
+}
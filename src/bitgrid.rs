@@ -0,0 +1,341 @@
+//! The bitgrid module provides `BitGrid`, a bit-packed grid of cells which can be stepped
+//! 64 cells at a time using word-parallel (bitwise) arithmetic.
+
+use grid::{ Grid, Cell };
+use rules::{ LifeLike, Edges };
+use bands::for_each_band;
+
+const BITS: usize = 64;
+
+/// A grid of cells packed 64 to a `u64`, with each row starting on a new word.
+///
+/// Bits beyond the width of the grid in the last word of each row are always zero.
+#[derive(PartialEq, Clone, Debug)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Constructs a dead `BitGrid` of `width` and `height`
+    pub fn create_dead(width: usize, height: usize) -> BitGrid {
+        let stride = width.div_ceil(BITS);
+        BitGrid { width, height, stride, words: vec![0; stride * height] }
+    }
+
+    /// Constructs a `BitGrid` from the cells of a `Grid`
+    pub fn from_grid(grid: &Grid) -> BitGrid {
+        let mut bits = BitGrid::create_dead(grid.width(), grid.height());
+        bits.load(grid);
+        bits
+    }
+
+    /// Gets the width of this `BitGrid`
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height of this `BitGrid`
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns true if the cell at the given coordinates is live
+    #[inline]
+    pub fn is_live(&self, x: usize, y: usize) -> bool {
+        assert!(x < self.width && y < self.height, "Coordinates ({}, {}) out of range", x, y);
+        self.words[y * self.stride + x / BITS] & (1 << (x % BITS)) != 0
+    }
+
+    /// Sets the cell at the given coordinates to live or dead
+    #[inline]
+    pub fn set_live(&mut self, x: usize, y: usize, live: bool) {
+        assert!(x < self.width && y < self.height, "Coordinates ({}, {}) out of range", x, y);
+        let word = &mut self.words[y * self.stride + x / BITS];
+        if live {
+            *word |= 1 << (x % BITS);
+        }
+        else {
+            *word &= !(1 << (x % BITS));
+        }
+    }
+
+    /// Gets the number of live cells in this `BitGrid`
+    pub fn population(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Counts the cells born and the cells which died between this `BitGrid` and `next`,
+    /// which must have the same dimensions
+    pub fn count_changes(&self, next: &BitGrid) -> (usize, usize) {
+        assert!(next.width == self.width && next.height == self.height,
+                "BitGrid dimensions do not match");
+        self.words.iter().zip(&next.words).fold((0, 0), |(births, deaths), (old, new)| {
+            (births + (!old & new).count_ones() as usize, deaths + (old & !new).count_ones() as usize)
+        })
+    }
+
+    /// Overwrites every cell with the cells of a `Grid` of the same dimensions
+    pub fn load(&mut self, grid: &Grid) {
+        assert!(grid.width() == self.width && grid.height() == self.height,
+                "Grid dimensions do not match");
+        for (row, words) in grid.iter_rows().zip(self.words.chunks_mut(self.stride.max(1))) {
+            for (chunk, word) in row.chunks(BITS).zip(words.iter_mut()) {
+                *word = chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |w, (i, c)| if c.is_live() { w | (1 << i) } else { w });
+            }
+        }
+    }
+
+    /// Overwrites every cell of a `Grid` of the same dimensions with the cells of this `BitGrid`
+    pub fn store(&self, grid: &mut Grid) {
+        assert!(grid.width() == self.width && grid.height() == self.height,
+                "Grid dimensions do not match");
+        if self.width == 0 {
+            return;
+        }
+        for (row, words) in grid.cells_mut().chunks_mut(self.width).zip(self.words.chunks(self.stride)) {
+            for (chunk, word) in row.chunks_mut(BITS).zip(words) {
+                for (i, cell) in chunk.iter_mut().enumerate() {
+                    *cell = if word & (1 << i) != 0 { Cell::Live } else { Cell::Dead };
+                }
+            }
+        }
+    }
+
+    /// Constructs a `Grid` from the cells of this `BitGrid`
+    pub fn to_grid(&self) -> Grid {
+        let mut grid = Grid::create_dead(self.width, self.height);
+        self.store(&mut grid);
+        grid
+    }
+
+    /// Executes a single step of a life-like `rule` over the Moore neighbourhood,
+    /// treating the edges of the grid as described by `edges`, and returns the result
+    pub fn step(&self, rule: &LifeLike, edges: Edges) -> BitGrid {
+        let mut next = BitGrid::create_dead(self.width, self.height);
+        self.step_into(rule, edges, &mut next);
+        next
+    }
+
+    /// Executes a single step of a life-like `rule` over the Moore neighbourhood,
+    /// treating the edges of the grid as described by `edges`, and writes the result
+    /// into `next`, which must have the same dimensions
+    pub fn step_into(&self, rule: &LifeLike, edges: Edges, next: &mut BitGrid) {
+        self.step_into_parallel(rule, edges, next, 1);
+    }
+
+    /// As `step_into`, but splits the grid into horizontal bands which are stepped
+    /// on up to `threads` threads
+    pub fn step_into_parallel(&self, rule: &LifeLike, edges: Edges, next: &mut BitGrid, threads: usize) {
+        assert!(next.width == self.width && next.height == self.height,
+                "BitGrid dimensions do not match");
+        for_each_band(&mut next.words, self.stride, self.height, threads, |first, band| {
+            self.step_rows(rule, edges, first, band)
+        });
+    }
+
+    /// Steps the rows starting at row `first`, writing them into `out`, which holds
+    /// a whole number of rows
+    fn step_rows(&self, rule: &LifeLike, edges: Edges, first: usize, out: &mut [u64]) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let counts = CountMasks::new(rule);
+        let zero = vec![0; self.stride];
+        let row = |y: usize| &self.words[y * self.stride .. (y + 1) * self.stride];
+        let torus = edges == Edges::Torus;
+
+        for (out_row, y) in out.chunks_mut(self.stride).zip(first..) {
+            let above = if y > 0 { row(y - 1) } else if torus { row(self.height - 1) } else { &zero[..] };
+            let below = if y + 1 < self.height { row(y + 1) } else if torus { row(0) } else { &zero[..] };
+            let centre = row(y);
+
+            for (i, out) in out_row.iter_mut().enumerate() {
+                let (al, a, ar) = self.shifted(above, i, torus);
+                let (l, c, r) = self.shifted(centre, i, torus);
+                let (bl, b, br) = self.shifted(below, i, torus);
+
+                let count = add8([al, a, ar, l, r, bl, b, br]);
+                let mut result = (c & counts.survival(count)) | (!c & counts.birth(count));
+                if i + 1 == self.stride {
+                    result &= self.tail_mask();
+                }
+                *out = result;
+            }
+        }
+    }
+
+    /// Mask of the valid bits in the last word of each row
+    #[inline]
+    fn tail_mask(&self) -> u64 {
+        match self.width % BITS {
+            0 => !0,
+            n => (1 << n) - 1,
+        }
+    }
+
+    /// Returns word `i` of `row` along with copies shifted so that each bit holds its
+    /// left and right neighbour respectively
+    #[inline]
+    fn shifted(&self, row: &[u64], i: usize, torus: bool) -> (u64, u64, u64) {
+        let last_bit = (self.width - 1) % BITS;
+        let c = row[i];
+
+        let left_carry =
+            if i > 0 { row[i - 1] >> (BITS - 1) }
+            else if torus { (row[self.stride - 1] >> last_bit) & 1 }
+            else { 0 };
+
+        let mut right = c >> 1;
+        if i + 1 < self.stride {
+            right |= row[i + 1] << (BITS - 1);
+        }
+        else if torus {
+            right |= (row[0] & 1) << last_bit;
+        }
+
+        ((c << 1) | left_carry, c, right)
+    }
+}
+
+/// A neighbour count for 64 cells in parallel, as four bit planes
+#[derive(Clone, Copy)]
+struct Count([u64; 4]);
+
+/// Adds three one bit values, returning the sum and carry bits
+#[inline]
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let ab = a ^ b;
+    (ab ^ c, (a & b) | (ab & c))
+}
+
+/// Adds two one bit values, returning the sum and carry bits
+#[inline]
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+/// Counts the live neighbours for 64 cells in parallel using a tree of adders
+#[inline]
+fn add8(n: [u64; 8]) -> Count {
+    let (s0, c0) = full_add(n[0], n[1], n[2]);
+    let (s1, c1) = full_add(n[3], n[4], n[5]);
+    let (s2, c2) = half_add(n[6], n[7]);
+    let (bit0, c3) = full_add(s0, s1, s2);
+    let (s4, c4) = full_add(c0, c1, c2);
+    let (bit1, c5) = half_add(s4, c3);
+    let (bit2, bit3) = half_add(c4, c5);
+    Count([bit0, bit1, bit2, bit3])
+}
+
+/// The neighbour counts which cause birth and survival under a `LifeLike` rule
+struct CountMasks {
+    births: Vec<usize>,
+    survivals: Vec<usize>,
+}
+
+impl CountMasks {
+    fn new(rule: &LifeLike) -> CountMasks {
+        CountMasks {
+            births: (0..9).filter(|&n| rule.is_birth(n)).collect(),
+            survivals: (0..9).filter(|&n| rule.is_survival(n)).collect(),
+        }
+    }
+
+    /// Returns a mask of the cells whose count is equal to `n`
+    #[inline]
+    fn equals(count: Count, n: usize) -> u64 {
+        let plane = |bit: usize| if n & (1 << bit) != 0 { count.0[bit] } else { !count.0[bit] };
+        plane(0) & plane(1) & plane(2) & plane(3)
+    }
+
+    #[inline]
+    fn birth(&self, count: Count) -> u64 {
+        self.births.iter().fold(0, |m, &n| m | CountMasks::equals(count, n))
+    }
+
+    #[inline]
+    fn survival(&self, count: Count) -> u64 {
+        self.survivals.iter().fold(0, |m, &n| m | CountMasks::equals(count, n))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::BitGrid;
+    use grid::{ Grid, Cell };
+    use rules::{ self, LifeLike, Edges };
+    use world::World;
+
+    /// Constructs a pseudo-random grid using a linear congruential generator
+    pub fn make_random_grid(width: usize, height: usize, seed: u64) -> Grid {
+        let mut state = seed;
+        Grid::from_fn(width, height, |_, _| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            if state >> 62 == 0 { Cell::Live } else { Cell::Dead }
+        })
+    }
+
+    #[test]
+    fn can_round_trip_grid() {
+        for &(w, h) in &[(0, 0), (1, 1), (63, 2), (64, 3), (65, 4), (130, 5)] {
+            let grid = make_random_grid(w, h, 7);
+            let bits = BitGrid::from_grid(&grid);
+
+            assert_eq!(bits.to_grid(), grid);
+            assert_eq!(bits.population(), grid.iter_cells().filter(|c| c.2.is_live()).count());
+        }
+    }
+
+    #[test]
+    fn can_get_and_set_cells() {
+        let mut bits = BitGrid::create_dead(100, 2);
+
+        bits.set_live(70, 1, true);
+        assert!(bits.is_live(70, 1));
+        assert!(!bits.is_live(6, 1));
+
+        bits.set_live(70, 1, false);
+        assert_eq!(bits.population(), 0);
+    }
+
+    #[test]
+    fn matches_cell_stepping() {
+        let rules: Vec<LifeLike> = vec![
+            LifeLike::conway(),
+            "B36/S23".parse().unwrap(),
+            "B2/S".parse().unwrap(),
+            "B3678/S34678".parse().unwrap(),
+            "B0/S8".parse().unwrap(),
+        ];
+
+        for &(w, h) in &[(1, 1), (2, 3), (5, 5), (63, 7), (64, 9), (65, 3), (130, 6)] {
+            for rule in &rules {
+                let grid = make_random_grid(w, h, (w * h) as u64);
+                for &(edges, neighbours) in &[(Edges::Torus, rules::torus_neighbours as rules::NeighboursFn),
+                                              (Edges::Terminal, rules::terminal_neighbours)] {
+                    let mut world = World::new(grid.clone());
+                    world.set_rules(*rule).unwrap();
+                    world.set_neighbours(neighbours).unwrap();
+
+                    let mut bits = BitGrid::from_grid(&grid);
+                    for gen in 0..4 {
+                        world.step_mut();
+                        bits = bits.step(rule, edges);
+                        assert_eq!(&bits.to_grid(), world.grid(),
+                                   "{}x{} {} {:?} generation {}", w, h, rule, edges, gen);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Functions used for taking a census of the objects in a `Grid`.
+//!
+//! A grid is split into connected objects, each of which is simulated on its own under
+//! the standard rules to find whether it is a still life, an oscillator or a spaceship.
+//! Objects are compared regardless of their phase, rotation and reflection, and common
+//! objects are given their usual names, or their apgcode.
+
+use std::collections::{ BTreeMap, HashMap };
+use std::sync::OnceLock;
+
+use apgcode;
+use grid::{ Grid, Cell };
+use period::simulate;
+
+pub use period::Kind;
+
+/// Common objects under the standard rules, drawn with `O` for live cells
+const KNOWN_OBJECTS: &[(&str, &[&str])] = &[
+    ("block",            &["OO",
+                           "OO"]),
+    ("beehive",          &[".OO.",
+                           "O..O",
+                           ".OO."]),
+    ("loaf",             &[".OO.",
+                           "O..O",
+                           ".O.O",
+                           "..O."]),
+    ("boat",             &["OO.",
+                           "O.O",
+                           ".O."]),
+    ("ship",             &["OO.",
+                           "O.O",
+                           ".OO"]),
+    ("tub",              &[".O.",
+                           "O.O",
+                           ".O."]),
+    ("pond",             &[".OO.",
+                           "O..O",
+                           "O..O",
+                           ".OO."]),
+    ("long boat",        &["OO..",
+                           "O.O.",
+                           ".O.O",
+                           "..O."]),
+    ("barge",            &[".O..",
+                           "O.O.",
+                           ".O.O",
+                           "..O."]),
+    ("mango",            &[".OO..",
+                           "O..O.",
+                           ".O..O",
+                           "..OO."]),
+    ("eater 1",          &["OO..",
+                           "O.O.",
+                           "..O.",
+                           "..OO"]),
+    ("snake",            &["OO.O",
+                           "O.OO"]),
+    ("blinker",          &["OOO"]),
+    ("toad",             &[".OOO",
+                           "OOO."]),
+    ("beacon",           &["OO..",
+                           "OO..",
+                           "..OO",
+                           "..OO"]),
+    ("clock",            &["..O.",
+                           "O.O.",
+                           ".O.O",
+                           ".O.."]),
+    ("pulsar",           &["..OOO...OOO..",
+                           ".............",
+                           "O....O.O....O",
+                           "O....O.O....O",
+                           "O....O.O....O",
+                           "..OOO...OOO..",
+                           ".............",
+                           "..OOO...OOO..",
+                           "O....O.O....O",
+                           "O....O.O....O",
+                           "O....O.O....O",
+                           ".............",
+                           "..OOO...OOO.."]),
+    ("glider",           &[".O.",
+                           "..O",
+                           "OOO"]),
+    ("lightweight spaceship",  &[".O..O",
+                                 "O....",
+                                 "O...O",
+                                 "OOOO."]),
+    ("middleweight spaceship", &["...O..",
+                                 ".O...O",
+                                 "O.....",
+                                 "O....O",
+                                 "OOOOO."]),
+    ("heavyweight spaceship",  &["...OO..",
+                                 ".O....O",
+                                 "O......",
+                                 "O.....O",
+                                 "OOOOOO."]),
+];
+
+/// Returns the name of each object in `KNOWN_OBJECTS`, keyed by the form of the object
+/// given by `key`. The objects are only simulated the first time.
+fn known_objects() -> &'static HashMap<Grid, &'static str> {
+    static KNOWN: OnceLock<HashMap<Grid, &'static str>> = OnceLock::new();
+    KNOWN.get_or_init(|| {
+        KNOWN_OBJECTS
+            .iter()
+            .map(|&(name, rows)| (key(&simulate(&Grid::from_rows(rows)).1), name))
+            .collect()
+    })
+}
+
+/// A single connected object found in a `Grid`
+#[derive(Clone, Debug)]
+pub struct Object {
+    /// The column of the left edge of the object
+    pub x: usize,
+    /// The row of the top edge of the object
+    pub y: usize,
+    /// The live cells of the object, cropped to their bounding box
+    pub cells: Grid,
+    /// How the object behaves when simulated on its own
+    pub kind: Kind,
+    /// The usual name of the object, if it is a common one
+    pub name: Option<&'static str>,
+    /// The apgcode of the object, if it is not unstable
+    pub code: Option<String>,
+}
+
+impl Object {
+    /// Returns the name the object is tallied under: its usual name if it has one,
+    /// otherwise its apgcode, or a description of its kind and population if it is unstable
+    pub fn label(&self) -> String {
+        match (self.name, &self.code) {
+            (Some(name), _)    => name.to_owned(),
+            (None, Some(code)) => code.clone(),
+            (None, None)       => format!("{} ({} cells)", self.kind, self.cells.population()),
+        }
+    }
+}
+
+/// The objects found in a `Grid` by `take_census`
+#[derive(Clone, Debug)]
+pub struct Census {
+    objects: Vec<Object>,
+}
+
+impl Census {
+    /// Returns every object found, ordered by position from top to bottom
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// Returns the number of objects found under each label
+    pub fn tally(&self) -> BTreeMap<String, usize> {
+        let mut tally = BTreeMap::new();
+        for object in &self.objects {
+            *tally.entry(object.label()).or_insert(0) += 1;
+        }
+        tally
+    }
+}
+
+/// Splits `grid` into its connected objects and classifies each one.
+///
+/// Live cells belong to the same object if they are within `radius` cells of each other
+/// horizontally and vertically, so a `radius` of `1` joins cells which touch, including
+/// diagonally. Objects are simulated under the standard rules.
+pub fn take_census(grid: &Grid, radius: usize) -> Census {
+    let known = known_objects();
+    let objects = components(grid, radius)
+        .into_iter()
+        .map(|(x, y, cells)| {
+            let (kind, phases) = simulate(&cells);
+            let (name, code) = match kind {
+                Kind::Unstable => (None, None),
+                _ => {
+                    (known.get(&key(&phases)).cloned(), Some(apgcode::encode_phases(kind, &phases)))
+                },
+            };
+            Object { x, y, cells, kind, name, code }
+        })
+        .collect();
+
+    Census { objects }
+}
+
+/// Splits the live cells of `grid` into connected components, where cells are connected
+/// if they are within `radius` cells of each other horizontally and vertically.
+///
+/// Returns the position `(x, y)` of each component along with its cells, cropped to their
+/// bounding box.
+pub fn components(grid: &Grid, radius: usize) -> Vec<(usize, usize, Grid)> {
+    let (w, h) = (grid.width(), grid.height());
+    let mut visited = vec![false; w * h];
+    let mut found = Vec::new();
+
+    for (x, y, cell) in grid.iter_cells() {
+        if !cell.is_live() || visited[y * w + x] {
+            continue;
+        }
+        // Flood fill from this cell
+        visited[y * w + x] = true;
+        let mut stack = vec![(x, y)];
+        let mut members = Vec::new();
+        while let Some((cx, cy)) = stack.pop() {
+            members.push((cx, cy));
+            for ny in cy.saturating_sub(radius)..(cy + radius + 1).min(h) {
+                for nx in cx.saturating_sub(radius)..(cx + radius + 1).min(w) {
+                    if !visited[ny * w + nx] && grid.cell_at(nx, ny).is_live() {
+                        visited[ny * w + nx] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+        }
+
+        let x0 = members.iter().map(|m| m.0).min().unwrap();
+        let y0 = members.iter().map(|m| m.1).min().unwrap();
+        let x1 = members.iter().map(|m| m.0).max().unwrap();
+        let y1 = members.iter().map(|m| m.1).max().unwrap();
+        let mut cells = Grid::create_dead(x1 - x0 + 1, y1 - y0 + 1);
+        for (mx, my) in members {
+            cells.set_cell(mx - x0, my - y0, Cell::Live);
+        }
+        found.push((x0, y0, cells));
+    }
+
+    found
+}
+
+/// Returns the canonical orientation of `grid`: the least of its eight rotations and
+/// reflections, so that every orientation of the same object has the same canonical form
+pub fn canonical(grid: &Grid) -> Grid {
+    grid.symmetries()
+        .into_iter()
+        .min_by_key(sort_key)
+        .unwrap()
+}
+
+/// Returns a key which is the same for every phase and orientation of an object
+fn key(phases: &[Grid]) -> Grid {
+    phases.iter().map(canonical).min_by_key(sort_key).unwrap()
+}
+
+/// Orders grids by their dimensions, then by their cells in row order
+fn sort_key(grid: &Grid) -> (usize, usize, Vec<bool>) {
+    (grid.width(), grid.height(), grid.iter_cells().map(|(_, _, c)| c.is_live()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ take_census, components, canonical, simulate, Kind, KNOWN_OBJECTS };
+    use grid::{ Grid, Cell };
+    use grid::tests as grid_test;
+    use grid::tests::make_grid;
+
+    #[test]
+    fn known_objects_are_stable_and_named() {
+        // Some objects, such as the pulsar, have gaps wider than a single cell
+        for &(name, rows) in KNOWN_OBJECTS {
+            let census = take_census(&make_grid(rows), 2);
+
+            assert_eq!(census.objects().len(), 1, "{}", name);
+            assert_eq!(census.objects()[0].name, Some(name));
+        }
+    }
+
+    #[test]
+    fn can_classify_objects() {
+        let kind = |rows: &[&str]| simulate(&make_grid(rows)).0;
+
+        assert_eq!(kind(&["OO", "OO"]), Kind::Still);
+        assert_eq!(kind(&["OOO"]), Kind::Oscillator { period: 2 });
+        assert_eq!(kind(&[".O.", "..O", "OOO"]), Kind::Spaceship { period: 4, dx: 1, dy: 1 });
+        assert_eq!(kind(&[".O..O", "O....", "O...O", "OOOO."]), Kind::Spaceship { period: 4, dx: -2, dy: 0 });
+        assert_eq!(kind(&["OO", "O."]), Kind::Unstable);
+        assert_eq!(kind(&["O"]), Kind::Unstable);
+    }
+
+    #[test]
+    fn can_tally_objects() {
+        let mut grid = Grid::create_dead(30, 20);
+        grid.write_cells(0, 0, &make_grid(&["OO", "OO"]));
+        grid.write_cells(10, 0, &make_grid(&["OO", "OO"]));
+        grid.write_cells(20, 0, &make_grid(&["O", "O", "O"]));
+        grid.write_cells(0, 10, &make_grid(&[".O.", "O.O", "O.O", ".O."]));
+        grid.write_cells(10, 10, &grid_test::make_glider_grid());
+        grid.write_cells(20, 10, &make_grid(&["OO.", "O.O", "OO."]));
+
+        let census = take_census(&grid, 1);
+        let tally: Vec<_> = census.tally().into_iter().collect();
+
+        assert_eq!(tally, vec![("beehive".to_owned(), 1),
+                               ("blinker".to_owned(), 1),
+                               ("block".to_owned(), 2),
+                               ("glider".to_owned(), 1),
+                               ("unstable object (6 cells)".to_owned(), 1)]);
+        assert_eq!((census.objects()[1].x, census.objects()[1].y), (10, 0));
+    }
+
+    #[test]
+    fn adjacency_radius_joins_nearby_objects() {
+        let grid = make_grid(&["OO.OO",
+                               "OO.OO"]);
+
+        assert_eq!(components(&grid, 1).len(), 2);
+        assert_eq!(components(&grid, 2).len(), 1);
+
+        let census = take_census(&grid, 2);
+        assert_eq!(census.objects()[0].label(), "xs8_rr");
+    }
+
+    #[test]
+    fn dying_cells_are_not_components() {
+        let mut grid = make_grid(&["OO...", "OO..."]);
+        grid.set_cell(4, 0, Cell::Dying(2));
+
+        assert_eq!(components(&grid, 1).len(), 1);
+    }
+
+    #[test]
+    fn canonical_form_ignores_orientation() {
+        let glider = make_grid(&[".O.", "..O", "OOO"]);
+        let rotated = Grid::from_fn(3, 3, |x, y| glider.cell_at(y, 2 - x).clone());
+        let reflected = Grid::from_fn(3, 3, |x, y| glider.cell_at(2 - x, y).clone());
+
+        assert!(rotated != glider && reflected != glider);
+        assert_eq!(canonical(&glider), canonical(&rotated));
+        assert_eq!(canonical(&glider), canonical(&reflected));
+    }
+}
@@ -3,13 +3,43 @@
 use std::vec::Vec;
 use std::iter::Iterator;
 use std::option::Option;
-use std::fmt::{ Debug, Formatter, Error };
+use std::error;
+use std::fmt::{ self, Debug, Display, Formatter, Error };
 
-/// Represents a single Cell, alive or dead
-#[derive(PartialEq, Clone, Debug)]
-pub enum Cell { Live, Dead }
+use plaintext::Padding;
+
+/// Represents a single Cell, alive or dead.
+///
+/// Rules with more than two states, such as
+/// [Generations](http://conwaylife.com/wiki/Generations) rules, also use `Dying` cells,
+/// which are neither live nor dead. A `Dying` cell holds its state number, from 2 upwards,
+/// where dead cells are state 0 and live cells state 1.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum Cell { Live, Dead, Dying(u8) }
+
+/// The largest number of states supported, so that every state fits in a `Cell::Dying`
+pub(crate) const MAX_STATES: usize = 256;
 
 impl Cell {
+    /// Returns the cell with the given state number: 0 for dead, 1 for live and any
+    /// other state for dying
+    pub fn from_state(state: u8) -> Cell {
+        match state {
+            0 => Cell::Dead,
+            1 => Cell::Live,
+            n => Cell::Dying(n),
+        }
+    }
+
+    /// Returns the state number of this cell
+    pub fn state(&self) -> u8 {
+        match *self {
+            Cell::Dead     => 0,
+            Cell::Live     => 1,
+            Cell::Dying(n) => n,
+        }
+    }
+
     pub fn is_live(&self) -> bool {
         match self { &Cell::Live => true, _ => false }
     }
@@ -17,9 +47,126 @@ impl Cell {
     pub fn is_dead(&self) -> bool {
         match self { &Cell::Dead => true, _ => false }
     }
+
+    pub fn is_dying(&self) -> bool {
+        matches!(*self, Cell::Dying(_))
+    }
+}
+
+/// Describes why a `Grid` could not be constructed or accessed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GridError {
+    /// The coordinates `(x, y)` are outside of the grid
+    OutOfRange { x: usize, y: usize },
+    /// The number of cells given does not match the dimensions
+    InvalidDimensions { width: usize, height: usize, cells: usize },
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        use self::GridError::*;
+        match *self {
+            OutOfRange { x, y } => {
+                write!(f, "Coordinates ({}, {}) out of range", x, y)
+            },
+            InvalidDimensions { width, height, cells } => {
+                write!(f, "Invalid height and width: {}x{} grid with {} cells", width, height, cells)
+            },
+        }
+    }
+}
+
+impl error::Error for GridError {}
+
+/// Describes how `Grid::paste` combines the cells being pasted with the existing cells
+///
+/// The boolean modes `Or`, `And` and `Xor` only combine whether cells are live. Where the
+/// result is not live an existing live cell becomes dead, while an existing dead or
+/// `Dying` cell keeps its state, and pasted `Dying` cells are treated as not live.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PasteMode {
+    /// Overwrites every cell with the pasted cell
+    Copy,
+    /// Overwrites only the cells where the pasted cell is live
+    LiveOnly,
+    /// Cells are live if either the existing or the pasted cell is live
+    Or,
+    /// Cells are live if both the existing and the pasted cell are live
+    And,
+    /// Cells are live if exactly one of the existing and the pasted cell is live
+    Xor,
+}
+
+/// Returns `Live` if `live` is set, otherwise `Dead` if `existing` is live or `existing`
+/// unchanged if it is not
+fn live_if(live: bool, existing: &Cell) -> Cell {
+    match (live, existing) {
+        (true, _)           => Cell::Live,
+        (false, Cell::Live) => Cell::Dead,
+        (false, _)          => existing.clone(),
+    }
+}
+
+/// The point of a `Grid` which stays fixed when it is resized by `Grid::resize`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Centre,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Returns the horizontal and vertical alignment of this anchor, where `0` is the
+    /// start, `1` the middle and `2` the end of each dimension
+    fn alignment(self) -> (isize, isize) {
+        match self {
+            Anchor::TopLeft     => (0, 0),
+            Anchor::Top         => (1, 0),
+            Anchor::TopRight    => (2, 0),
+            Anchor::Left        => (0, 1),
+            Anchor::Centre      => (1, 1),
+            Anchor::Right       => (2, 1),
+            Anchor::BottomLeft  => (0, 2),
+            Anchor::Bottom      => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
 }
 
-#[derive(PartialEq, Clone)]
+/// One of the eight rotations and reflections of a `Grid`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Symmetry {
+    Identity,
+    /// Rotation by 90 degrees clockwise
+    Rotate90,
+    Rotate180,
+    /// Rotation by 270 degrees clockwise
+    Rotate270,
+    /// Reflection left to right
+    FlipHorizontal,
+    /// Reflection top to bottom
+    FlipVertical,
+    /// Reflection along the leading diagonal
+    Transpose,
+    /// Reflection along the trailing diagonal
+    AntiTranspose,
+}
+
+impl Symmetry {
+    /// Every symmetry, starting with `Identity`
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity, Symmetry::Rotate90, Symmetry::Rotate180, Symmetry::Rotate270,
+        Symmetry::FlipHorizontal, Symmetry::FlipVertical, Symmetry::Transpose, Symmetry::AntiTranspose,
+    ];
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
 /// An addressable grid of `Cell`s
 ///
 /// Provides a number of functions for constructing, modifying and walking `Cell` grids.
@@ -31,13 +178,23 @@ pub struct Grid {
 
 impl Grid {
     /// Constructs a Grid from raw components
+    ///
+    /// Panics if the number of cells in `state` does not match `width` and `height`.
+    /// See `try_from_raw` for a non-panicking alternative.
     pub fn from_raw(width: usize, height: usize, state: Vec<Cell>) -> Grid {
-        let count = width * height;
+        match Grid::try_from_raw(width, height, state) {
+            Ok(grid) => grid,
+            Err(e)   => panic!("{}", e),
+        }
+    }
 
-        if count != state.len() {
-            panic!("Invalid height and width");
+    /// Constructs a Grid from raw components, failing if the number of cells in `state`
+    /// does not match `width` and `height`
+    pub fn try_from_raw(width: usize, height: usize, state: Vec<Cell>) -> Result<Grid, GridError> {
+        if width.checked_mul(height) != Some(state.len()) {
+            return Err(GridError::InvalidDimensions { width, height, cells: state.len() });
         }
-        Grid { width: width, height: height, cells: state  }
+        Ok(Grid { width, height, cells: state })
     }
 
     /// Constructs a Grid of `width` and `height` using a factory function.
@@ -55,6 +212,14 @@ impl Grid {
         Grid { width: width, height: height, cells: vec![Cell::Dead; count] }
     }
 
+    /// Constructs a Grid from rows of `O` (live) and `.` (dead) characters
+    pub(crate) fn from_rows(rows: &[&str]) -> Grid {
+        let rows: Vec<&[u8]> = rows.iter().map(|row| row.as_bytes()).collect();
+        Grid::from_fn(rows[0].len(), rows.len(), |x, y| {
+            if rows[y][x] == b'O' { Cell::Live } else { Cell::Dead }
+        })
+    }
+
     /// Gets the width of this `Grid`
     #[inline]
     pub fn width(&self) -> usize {
@@ -68,42 +233,292 @@ impl Grid {
     }
 
     /// Returns a reference to the `Cell` at the given coordinates
+    ///
+    /// Panics if the coordinates are out of range. See `try_cell_at` for a non-panicking
+    /// alternative.
     #[inline]
     pub fn cell_at(&self, x: usize, y: usize) -> &Cell {
-        match self.cells.get(y * self.width + x) {
-            Some(c) => c,
-            None    => panic!("Coordinates ({}, {}) out of range", x, y),
+        match self.try_cell_at(x, y) {
+            Ok(c)  => c,
+            Err(e) => panic!("{}", e),
         }
     }
 
     /// Overwrites the `Cell` at the given coordinates with the given value
+    ///
+    /// Panics if the coordinates are out of range. See `try_set_cell` for a non-panicking
+    /// alternative.
     #[inline]
     pub fn set_cell(&mut self, x: usize, y: usize, cell: Cell) {
-        if let Some(c) = self.cells.get_mut(y * self.width + x) {
-            *c = cell;
+        if let Err(e) = self.try_set_cell(x, y, cell) {
+            panic!("{}", e);
         }
-        else {
-            panic!("Coordinates ({}, {}) out of range", x, y)
+    }
+
+    /// Returns a reference to the `Cell` at the given coordinates, failing if they are
+    /// outside of this `Grid`
+    #[inline]
+    pub fn try_cell_at(&self, x: usize, y: usize) -> Result<&Cell, GridError> {
+        if x >= self.width || y >= self.height {
+            return Err(GridError::OutOfRange { x, y });
         }
+        Ok(&self.cells[y * self.width + x])
+    }
+
+    /// Overwrites the `Cell` at the given coordinates with the given value, failing if
+    /// they are outside of this `Grid`
+    #[inline]
+    pub fn try_set_cell(&mut self, x: usize, y: usize, cell: Cell) -> Result<(), GridError> {
+        if x >= self.width || y >= self.height {
+            return Err(GridError::OutOfRange { x, y });
+        }
+        self.cells[y * self.width + x] = cell;
+        Ok(())
+    }
+
+    /// Returns a reference to the `Cell` at the given signed coordinates, which wrap
+    /// around the edges of this `Grid` as on a torus. For example `(-1, -1)` is the
+    /// bottom right cell.
+    ///
+    /// Panics if this `Grid` has no cells.
+    #[inline]
+    pub fn wrapping_cell_at(&self, x: isize, y: isize) -> &Cell {
+        assert!(!self.cells.is_empty(), "Grid has no cells");
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        &self.cells[y * self.width + x]
+    }
+
+    /// Overwrites the `Cell` at the given signed coordinates, which wrap around the edges
+    /// of this `Grid` as for `wrapping_cell_at`.
+    ///
+    /// Panics if this `Grid` has no cells.
+    #[inline]
+    pub fn wrapping_set_cell(&mut self, x: isize, y: isize, cell: Cell) {
+        assert!(!self.cells.is_empty(), "Grid has no cells");
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.cells[y * self.width + x] = cell;
     }
 
     /// Overwrite the cells starting at coords `(x, y)` with the data in the given `Grid`
     /// If any coordinates are outside the grid no action is taken.
     pub fn write_cells(&mut self, x: usize, y: usize, data: &Grid) {
-        for data_y in 0 .. data.height() {
-            for data_x in 0 .. data.width() {
+        self.paste(x as isize, y as isize, data, PasteMode::Copy, false);
+    }
+
+    /// Combines the data in the given `Grid` into the cells starting at coords `(x, y)`
+    /// according to `mode`.
+    ///
+    /// The coordinates may be negative, placing the data partially beyond the top or left
+    /// edges. Data beyond the edges of the grid is discarded unless `wrap` is set, in which
+    /// case it wraps around to the opposite edge, as on a torus.
+    pub fn paste(&mut self, x: isize, y: isize, data: &Grid, mode: PasteMode, wrap: bool) {
+        let (w, h) = (self.width as isize, self.height as isize);
+        if w == 0 || h == 0 {
+            return;
+        }
+        for (data_x, data_y, cell) in data.iter_cells() {
+            let (mut grid_x, mut grid_y) = (x + data_x as isize, y + data_y as isize);
+            if wrap {
+                grid_x = grid_x.rem_euclid(w);
+                grid_y = grid_y.rem_euclid(h);
+            }
+            else if grid_x < 0 || grid_y < 0 || grid_x >= w || grid_y >= h {
+                continue;
+            }
 
-                let grid_y = y + data_y;
-                let grid_x = x + data_x;
+            let target = &mut self.cells[grid_y as usize * self.width + grid_x as usize];
+            *target = match mode {
+                PasteMode::Copy     => cell.clone(),
+                PasteMode::LiveOnly => if cell.is_live() { cell.clone() } else { target.clone() },
+                PasteMode::Or       => live_if(target.is_live() || cell.is_live(), target),
+                PasteMode::And      => live_if(target.is_live() && cell.is_live(), target),
+                PasteMode::Xor      => live_if(target.is_live() != cell.is_live(), target),
+            };
+        }
+    }
+
+    /// Returns the `width` by `height` region of this `Grid` whose top left cell is at
+    /// `(x, y)`. The region is clipped to the bounds of this `Grid`.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Grid {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        Grid::from_fn(width, height, |cx, cy| self.cell_at(x + cx, y + cy).clone())
+    }
+
+    /// Returns the smallest region of this `Grid` containing every cell which is not dead,
+    /// or an empty `Grid` if every cell is dead
+    pub fn trim(&self) -> Grid {
+        match self.occupied_bounds() {
+            Some((x, y, width, height)) => self.crop(x, y, width, height),
+            None                        => Grid::create_dead(0, 0),
+        }
+    }
+
+    /// Returns a copy of this `Grid` surrounded by dead margins of the given `padding`
+    pub fn pad(&self, padding: Padding) -> Grid {
+        let width = self.width + padding.left + padding.right;
+        let height = self.height + padding.top + padding.bottom;
+        let mut grid = Grid::create_dead(width, height);
+        grid.write_cells(padding.left, padding.top, self);
+        grid
+    }
+
+    /// Returns a copy of this `Grid` resized to `width` and `height`, keeping its cells
+    /// positioned relative to `anchor`. Dead cells fill any new space, and cells beyond
+    /// the new bounds are discarded.
+    pub fn resize(&self, width: usize, height: usize, anchor: Anchor) -> Grid {
+        // Returns the offset of the existing cells along a dimension
+        fn offset(old: usize, new: usize, align: isize) -> isize {
+            let slack = new as isize - old as isize;
+            match align {
+                0 => 0,
+                1 => slack / 2,
+                _ => slack,
+            }
+        }
+        let (align_x, align_y) = anchor.alignment();
+        let mut grid = Grid::create_dead(width, height);
+        grid.paste(offset(self.width, width, align_x), offset(self.height, height, align_y),
+                   self, PasteMode::Copy, false);
+        grid
+    }
+
+    /// Gets the number of live cells in this `Grid`
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|c| c.is_live()).count()
+    }
 
-                if grid_x >= self.width || grid_y >= self.height {
-                    continue;
-                }
+    /// Returns the bounds `(x, y, width, height)` of the smallest region containing
+    /// every live cell, or `None` if every cell is dead
+    pub fn live_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        self.bounds_where(Cell::is_live)
+    }
+
+    /// Returns the bounds `(x, y, width, height)` of the smallest region containing
+    /// every live or dying cell, or `None` if every cell is dead
+    pub fn occupied_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        self.bounds_where(|cell| !cell.is_dead())
+    }
 
-                let cell = data.cell_at(data_x, data_y);
-                self.set_cell(grid_x, grid_y, cell.clone());
+    fn bounds_where<F>(&self, include: F) -> Option<(usize, usize, usize, usize)>
+        where F: Fn(&Cell) -> bool
+    {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (x, y, cell) in self.iter_cells() {
+            if include(cell) {
+                bounds = Some(match bounds {
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                    None                   => (x, y, x, y),
+                });
             }
         }
+        bounds.map(|(x0, y0, x1, y1)| (x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+    }
+
+    /// Returns the mean coordinates `(x, y)` of every live cell, or `None` if every cell
+    /// is dead
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        let (count, sum_x, sum_y) = self.iter_cells()
+            .filter(|&(_, _, cell)| cell.is_live())
+            .fold((0, 0, 0), |(n, sx, sy), (x, y, _)| (n + 1, sx + x, sy + y));
+        if count == 0 {
+            return None;
+        }
+        Some((sum_x as f64 / count as f64, sum_y as f64 / count as f64))
+    }
+
+    /// Returns a copy of this `Grid` transformed by `symmetry`
+    pub fn transform(&self, symmetry: Symmetry) -> Grid {
+        let (w, h) = (self.width, self.height);
+        match symmetry {
+            Symmetry::Identity       => self.clone(),
+            Symmetry::Rotate90       => Grid::from_fn(h, w, |x, y| self.cell_at(y, h - 1 - x).clone()),
+            Symmetry::Rotate180      => Grid::from_fn(w, h, |x, y| self.cell_at(w - 1 - x, h - 1 - y).clone()),
+            Symmetry::Rotate270      => Grid::from_fn(h, w, |x, y| self.cell_at(w - 1 - y, x).clone()),
+            Symmetry::FlipHorizontal => Grid::from_fn(w, h, |x, y| self.cell_at(w - 1 - x, y).clone()),
+            Symmetry::FlipVertical   => Grid::from_fn(w, h, |x, y| self.cell_at(x, h - 1 - y).clone()),
+            Symmetry::Transpose      => Grid::from_fn(h, w, |x, y| self.cell_at(y, x).clone()),
+            Symmetry::AntiTranspose  => Grid::from_fn(h, w, |x, y| self.cell_at(w - 1 - y, h - 1 - x).clone()),
+        }
+    }
+
+    /// Returns a copy of this `Grid` rotated 90 degrees clockwise
+    pub fn rotate_90(&self) -> Grid {
+        self.transform(Symmetry::Rotate90)
+    }
+
+    /// Returns a copy of this `Grid` rotated 180 degrees
+    pub fn rotate_180(&self) -> Grid {
+        self.transform(Symmetry::Rotate180)
+    }
+
+    /// Returns a copy of this `Grid` rotated 270 degrees clockwise (90 degrees anticlockwise)
+    pub fn rotate_270(&self) -> Grid {
+        self.transform(Symmetry::Rotate270)
+    }
+
+    /// Returns a copy of this `Grid` mirrored left to right
+    pub fn flip_horizontal(&self) -> Grid {
+        self.transform(Symmetry::FlipHorizontal)
+    }
+
+    /// Returns a copy of this `Grid` mirrored top to bottom
+    pub fn flip_vertical(&self) -> Grid {
+        self.transform(Symmetry::FlipVertical)
+    }
+
+    /// Returns a copy of this `Grid` mirrored along its leading diagonal, so that rows
+    /// become columns
+    pub fn transpose(&self) -> Grid {
+        self.transform(Symmetry::Transpose)
+    }
+
+    /// Returns the eight rotations and reflections of this `Grid`, in the order of
+    /// `Symmetry::ALL`
+    pub fn symmetries(&self) -> Vec<Grid> {
+        Symmetry::ALL.iter().map(|&s| self.transform(s)).collect()
+    }
+
+    /// Returns a copy of this `Grid` with every cell moved by `(dx, dy)`.
+    ///
+    /// Cells moved beyond an edge wrap around to the opposite edge, as on a torus.
+    pub fn translate(&self, dx: isize, dy: isize) -> Grid {
+        let (w, h) = (self.width as isize, self.height as isize);
+        Grid::from_fn(self.width, self.height, |x, y| {
+            let src_x = (x as isize - dx).rem_euclid(w);
+            let src_y = (y as isize - dy).rem_euclid(h);
+            self.cell_at(src_x as usize, src_y as usize).clone()
+        })
+    }
+
+    /// Returns a copy of this `Grid` with every cell moved by `(dx, dy)`.
+    ///
+    /// Cells moved beyond an edge are discarded, and dead cells fill the space left behind.
+    pub fn shift(&self, dx: isize, dy: isize) -> Grid {
+        let (w, h) = (self.width as isize, self.height as isize);
+        Grid::from_fn(self.width, self.height, |x, y| {
+            let (src_x, src_y) = (x as isize - dx, y as isize - dy);
+            if src_x < 0 || src_y < 0 || src_x >= w || src_y >= h {
+                Cell::Dead
+            }
+            else {
+                self.cell_at(src_x as usize, src_y as usize).clone()
+            }
+        })
+    }
+
+    /// Returns a slice of every `Cell` in this `Grid`, in row order
+    #[inline]
+    pub(crate) fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Returns a mutable slice of every `Cell` in this `Grid`, in row order
+    #[inline]
+    pub(crate) fn cells_mut(&mut self) -> &mut [Cell] {
+        &mut self.cells
     }
 
     /// Returns an iterator over rows in this `Grid`
@@ -125,7 +540,11 @@ impl Debug for Grid {
         for row in self.iter_rows() {
             try!(write!(f, "\n"));
             for cell in row {
-                try!(write!(f, "{}", if cell.is_live() { "#" } else { "." }));
+                try!(write!(f, "{}", match *cell {
+                    Cell::Live     => "#",
+                    Cell::Dead     => ".",
+                    Cell::Dying(_) => "+",
+                }));
             }
         }
 
@@ -253,6 +672,55 @@ pub mod tests {
         Grid::from_raw(6, 5, state)
     }
 
+    /// Constructs a `Grid` from rows of `O` (live) and `.` (dead) characters
+    pub fn make_grid(rows: &[&str]) -> Grid {
+        Grid::from_rows(rows)
+    }
+
+    pub fn make_blinker_grid() -> Grid {
+        make_grid(&[".....",
+                    ".....",
+                    ".OOO.",
+                    ".....",
+                    "....."])
+    }
+
+    pub fn make_toad_grid() -> Grid {
+        make_grid(&["......",
+                    "......",
+                    "..OOO.",
+                    ".OOO..",
+                    "......",
+                    "......"])
+    }
+
+    pub fn make_pulsar_grid() -> Grid {
+        make_grid(&[".................",
+                    "...OOO...OOO.....",
+                    ".................",
+                    ".O....O.O....O...",
+                    ".O....O.O....O...",
+                    ".O....O.O....O...",
+                    "...OOO...OOO.....",
+                    ".................",
+                    "...OOO...OOO.....",
+                    ".O....O.O....O...",
+                    ".O....O.O....O...",
+                    ".O....O.O....O...",
+                    ".................",
+                    "...OOO...OOO.....",
+                    ".................",
+                    ".................",
+                    "................."])
+    }
+
+    pub fn make_lwss_grid() -> Grid {
+        make_grid(&[".O..O",
+                    "O....",
+                    "O...O",
+                    "OOOO."])
+    }
+
     #[test]
     fn can_create_grid_from_fn() {
 
@@ -298,4 +766,312 @@ pub mod tests {
         Grid::from_raw(10, 10, state);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn can_measure_live_cells() {
+
+        let grid = make_glider_grid();
+
+        assert_eq!(grid.population(), 5);
+        assert_eq!(grid.live_bounds(), Some((1, 1, 3, 3)));
+        assert_eq!(grid.centroid(), Some((2.4, 2.2)));
+
+        let grid = Grid::create_dead(4, 4);
+
+        assert_eq!(grid.population(), 0);
+        assert_eq!(grid.live_bounds(), None);
+        assert_eq!(grid.centroid(), None);
+    }
+
+    #[test]
+    fn dying_cells_are_neither_live_nor_dead() {
+        use super::Cell;
+
+        let mut grid = make_glider_grid();
+        grid.set_cell(4, 4, Cell::Dying(2));
+
+        assert_eq!(grid.population(), 5);
+        assert_eq!(grid.live_bounds(), Some((1, 1, 3, 3)));
+        assert_eq!(grid.occupied_bounds(), Some((1, 1, 4, 4)));
+        assert_eq!(grid.trim().height(), 4);
+
+        for state in 0..4 {
+            assert_eq!(Cell::from_state(state).state(), state);
+        }
+        assert!(Cell::from_state(3).is_dying());
+    }
+
+    #[test]
+    fn can_rotate_and_flip_glider() {
+        use super::Cell::Dead as X;
+        use super::Cell::Live as O;
+
+        let glider = make_glider_grid();
+
+        assert_eq!(glider.rotate_90(), Grid::from_raw(5, 6, vec![
+            X, X, X, X, X,
+            X, X, O, X, X,
+            X, O, X, X, X,
+            X, O, O, O, X,
+            X, X, X, X, X,
+            X, X, X, X, X,
+        ]));
+        assert_eq!(glider.flip_horizontal(), Grid::from_raw(6, 5, vec![
+            X, X, X, X, X, X,
+            X, X, O, X, X, X,
+            X, X, O, X, O, X,
+            X, X, O, O, X, X,
+            X, X, X, X, X, X,
+        ]));
+        assert_eq!(glider.flip_vertical(), Grid::from_raw(6, 5, vec![
+            X, X, X, X, X, X,
+            X, X, O, O, X, X,
+            X, O, X, O, X, X,
+            X, X, X, O, X, X,
+            X, X, X, X, X, X,
+        ]));
+        assert_eq!(glider.transpose(), Grid::from_raw(5, 6, vec![
+            X, X, X, X, X,
+            X, X, O, X, X,
+            X, X, X, O, X,
+            X, O, O, O, X,
+            X, X, X, X, X,
+            X, X, X, X, X,
+        ]));
+    }
+
+    #[test]
+    fn rotations_and_reflections_compose() {
+        let glider = make_glider_grid();
+
+        assert_eq!(glider.rotate_90().rotate_90(), glider.rotate_180());
+        assert_eq!(glider.rotate_180().rotate_90(), glider.rotate_270());
+        assert_eq!(glider.rotate_270().rotate_90(), glider);
+        assert_eq!(glider.transpose().transpose(), glider);
+        assert_eq!(glider.flip_horizontal().flip_vertical(), glider.rotate_180());
+        assert_eq!(glider.transpose().rotate_180(), glider.transform(super::Symmetry::AntiTranspose));
+    }
+
+    #[test]
+    fn symmetries_are_distinct_for_glider() {
+        let symmetries = make_glider_grid().symmetries();
+
+        assert_eq!(symmetries.len(), 8);
+        assert_eq!(symmetries[0], make_glider_grid());
+        for (i, a) in symmetries.iter().enumerate() {
+            for b in &symmetries[i + 1..] {
+                assert!(a != b);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetries_commute_with_stepping() {
+        use world::World;
+
+        let glider = make_glider_grid();
+        let stepped = World::new(glider.clone()).step().step().grid().clone();
+
+        for (grid, expected) in glider.symmetries().iter().zip(stepped.symmetries()) {
+            assert_eq!(World::new(grid.clone()).step().step().grid(), &expected);
+        }
+    }
+
+    #[test]
+    fn can_translate_on_torus() {
+        use world::World;
+
+        let glider = make_glider_grid();
+        let mut world = World::new(glider.clone());
+        for _ in 0..4 {
+            world.step_mut();
+        }
+
+        // A glider moves one cell diagonally every four generations
+        assert_eq!(&glider.translate(1, 1), world.grid());
+        assert_eq!(world.grid().translate(-1, -1), glider);
+        assert_eq!(glider.translate(6, -5), glider);
+        assert_eq!(glider.translate(3, 0).translate(3, 0), glider);
+    }
+
+    #[test]
+    fn can_shift_without_wrapping() {
+        let glider = make_glider_grid();
+
+        assert_eq!(glider.shift(1, 1), glider.translate(1, 1));
+        assert_eq!(glider.shift(3, 0).population(), 2);
+        assert_eq!(glider.shift(-6, 0), Grid::create_dead(6, 5));
+    }
+
+    #[test]
+    fn can_paste_with_boolean_modes() {
+        use super::PasteMode;
+
+        let block = Grid::from_raw(2, 2, vec![Live, Live, Live, Live]);
+        let diagonal = Grid::from_raw(2, 2, vec![Live, Dead, Dead, Live]);
+
+        let paste = |mode| {
+            let mut grid = diagonal.clone();
+            grid.paste(0, 0, &Grid::from_raw(2, 1, vec![Live, Dead]), mode, false);
+            grid
+        };
+        assert_eq!(paste(PasteMode::Copy), Grid::from_raw(2, 2, vec![Live, Dead, Dead, Live]));
+        assert_eq!(paste(PasteMode::Or), Grid::from_raw(2, 2, vec![Live, Dead, Dead, Live]));
+        assert_eq!(paste(PasteMode::And), Grid::from_raw(2, 2, vec![Live, Dead, Dead, Live]));
+        assert_eq!(paste(PasteMode::Xor), Grid::from_raw(2, 2, vec![Dead, Dead, Dead, Live]));
+
+        let mut grid = diagonal.clone();
+        grid.paste(0, 0, &block, PasteMode::Xor, false);
+        assert_eq!(grid, Grid::from_raw(2, 2, vec![Dead, Live, Live, Dead]));
+        grid.paste(0, 0, &block, PasteMode::Xor, false);
+        assert_eq!(grid, diagonal);
+
+        let mut grid = block.clone();
+        grid.paste(0, 0, &diagonal, PasteMode::LiveOnly, false);
+        assert_eq!(grid, block);
+        grid.paste(0, 0, &diagonal, PasteMode::And, false);
+        assert_eq!(grid, diagonal);
+    }
+
+    #[test]
+    fn boolean_paste_modes_keep_dying_cells() {
+        use super::PasteMode;
+        use super::Cell::Dying;
+
+        let grid = Grid::from_raw(4, 1, vec![Dying(2), Dying(2), Live, Dead]);
+        let data = Grid::from_raw(4, 1, vec![Live, Dead, Dying(3), Dying(3)]);
+
+        let paste = |mode| {
+            let mut grid = grid.clone();
+            grid.paste(0, 0, &data, mode, false);
+            grid
+        };
+        assert_eq!(paste(PasteMode::Or), Grid::from_raw(4, 1, vec![Live, Dying(2), Live, Dead]));
+        assert_eq!(paste(PasteMode::And), Grid::from_raw(4, 1, vec![Dying(2), Dying(2), Dead, Dead]));
+        assert_eq!(paste(PasteMode::Xor), Grid::from_raw(4, 1, vec![Live, Dying(2), Live, Dead]));
+    }
+
+    #[test]
+    fn can_paste_at_negative_offsets() {
+        use super::PasteMode;
+
+        let mut grid = Grid::create_dead(6, 5);
+        grid.paste(-1, -1, &make_glider_grid(), PasteMode::Or, false);
+
+        assert_eq!(grid, make_glider_grid().shift(-1, -1));
+    }
+
+    #[test]
+    fn can_paste_with_wrapping() {
+        use super::PasteMode;
+
+        let glider = make_glider_grid();
+        let mut grid = Grid::create_dead(6, 5);
+        grid.paste(-4, 3, &glider, PasteMode::Copy, true);
+
+        assert_eq!(grid, glider.translate(-4, 3));
+
+        let mut clipped = Grid::create_dead(6, 5);
+        clipped.paste(-4, 3, &glider, PasteMode::Copy, false);
+        assert_eq!(clipped, glider.shift(-4, 3));
+    }
+
+    #[test]
+    fn can_crop_and_trim() {
+        let glider = make_glider_grid();
+
+        assert_eq!(glider.crop(1, 1, 3, 3), Grid::from_raw(3, 3, vec![
+            Dead, Dead, Live,
+            Live, Dead, Live,
+            Dead, Live, Live,
+        ]));
+        assert_eq!(glider.trim(), glider.crop(1, 1, 3, 3));
+        assert_eq!(glider.crop(4, 3, 10, 10), Grid::create_dead(2, 2));
+        assert_eq!(glider.crop(7, 0, 1, 1), Grid::create_dead(0, 1));
+        assert_eq!(Grid::create_dead(3, 3).trim(), Grid::create_dead(0, 0));
+    }
+
+    #[test]
+    fn can_pad() {
+        use plaintext::Padding;
+
+        let glider = make_glider_grid();
+        let trimmed = glider.trim();
+
+        assert_eq!(trimmed.pad(Padding::new(1, 2, 1, 1)), glider);
+        assert_eq!(trimmed.pad(Padding::new(0, 0, 0, 0)), trimmed);
+    }
+
+    #[test]
+    fn can_resize_around_anchor() {
+        use super::Anchor;
+        use plaintext::Padding;
+
+        let block = Grid::from_raw(2, 2, vec![Live, Live, Live, Live]);
+
+        assert_eq!(block.resize(4, 4, Anchor::TopLeft), block.pad(Padding::new(0, 2, 2, 0)));
+        assert_eq!(block.resize(4, 4, Anchor::Centre), block.pad(Padding::new(1, 1, 1, 1)));
+        assert_eq!(block.resize(5, 3, Anchor::BottomRight), block.pad(Padding::new(1, 0, 0, 3)));
+        assert_eq!(block.resize(3, 4, Anchor::Top), block.pad(Padding::new(0, 1, 2, 0)));
+
+        let glider = make_glider_grid();
+        assert_eq!(glider.resize(3, 3, Anchor::Centre), glider.crop(1, 1, 3, 3));
+        assert_eq!(glider.resize(3, 2, Anchor::BottomRight), glider.crop(3, 3, 3, 2));
+    }
+
+    #[test]
+    fn can_access_cells_fallibly() {
+        use super::GridError;
+
+        let mut grid = make_glider_grid();
+
+        assert_eq!(grid.try_cell_at(3, 1), Ok(&Live));
+        assert_eq!(grid.try_cell_at(6, 0), Err(GridError::OutOfRange { x: 6, y: 0 }));
+        assert_eq!(grid.try_cell_at(0, 5), Err(GridError::OutOfRange { x: 0, y: 5 }));
+
+        assert_eq!(grid.try_set_cell(0, 0, Live), Ok(()));
+        assert_eq!(grid.cell_at(0, 0), &Live);
+        assert_eq!(grid.try_set_cell(0, 9, Live), Err(GridError::OutOfRange { x: 0, y: 9 }));
+        assert_eq!(GridError::OutOfRange { x: 0, y: 9 }.to_string(), "Coordinates (0, 9) out of range");
+    }
+
+    #[test]
+    fn can_create_grid_from_raw_fallibly() {
+        use super::GridError;
+
+        assert_eq!(Grid::try_from_raw(2, 1, vec![Live, Dead]), Ok(Grid::from_raw(2, 1, vec![Live, Dead])));
+        assert_eq!(Grid::try_from_raw(2, 2, vec![Live, Dead]),
+                   Err(GridError::InvalidDimensions { width: 2, height: 2, cells: 2 }));
+        // The product of the dimensions wraps around to the number of cells
+        let width = usize::MAX / 2 + 2;
+        assert_eq!(Grid::try_from_raw(width, 2, vec![Live, Dead]),
+                   Err(GridError::InvalidDimensions { width, height: 2, cells: 2 }));
+    }
+
+    #[test]
+    fn can_get_cells_with_wrapping() {
+        let grid = make_glider_grid();
+
+        assert_eq!(grid.wrapping_cell_at(3, 1), &Live);
+        assert_eq!(grid.wrapping_cell_at(-3, -4), &Live);
+        assert_eq!(grid.wrapping_cell_at(9, 11), &Live);
+        assert_eq!(grid.wrapping_cell_at(-1, -1), grid.cell_at(5, 4));
+    }
+
+    #[test]
+    fn can_set_cells_with_wrapping() {
+        let mut grid = Grid::create_dead(3, 2);
+        grid.wrapping_set_cell(-1, -1, Live);
+        grid.wrapping_set_cell(3, 5, Live);
+
+        assert_eq!(grid.cell_at(2, 1), &Live);
+        assert_eq!(grid.cell_at(0, 1), &Live);
+        assert_eq!(grid.population(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Coordinates (3, 0) out of range")]
+    fn cell_at_does_not_wrap_into_the_next_row() {
+        Grid::create_dead(3, 2).cell_at(3, 0);
+    }
+
+}
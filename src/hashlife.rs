@@ -0,0 +1,614 @@
+//! A [HashLife](http://conwaylife.com/wiki/HashLife) engine for advancing life-like
+//! patterns by very large numbers of generations.
+//!
+//! The universe is an unbounded plane represented as a canonicalised quadtree. Identical
+//! subtrees are shared, and the future of each subtree is memoised, so that repetitive
+//! patterns can be advanced by billions of generations in a fraction of a second.
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::mem;
+
+use grid::{ Grid, Cell };
+use rules::LifeLike;
+
+/// Identifies a canonical node in the quadtree
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct NodeId(u32);
+
+/// The dead leaf node
+const DEAD: NodeId = NodeId(0);
+/// The live leaf node
+const LIVE: NodeId = NodeId(1);
+
+/// The largest level the root may grow to before coordinates overflow `i64`
+const MAX_LEVEL: u32 = 62;
+
+/// The largest `log2` that `step_pow2` accepts, as the root is grown to at least three
+/// levels above it
+const MAX_STEP_LOG2: u32 = MAX_LEVEL - 3;
+
+/// The default number of nodes above which the cache is cleared before each step
+const DEFAULT_CACHE_LIMIT: usize = 1 << 22;
+
+/// A square quadtree node of `2^level` cells on each side
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    level: u32,
+    /// The `nw`, `ne`, `sw` and `se` quadrants. Unused for leaves.
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// Describes why a `HashLife` universe could not be advanced
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StepError {
+    /// The universe would grow beyond the coordinates which fit in an `i64`
+    TooLarge,
+    /// The generation count would no longer fit in a `u64`
+    GenerationOverflow,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::StepError::*;
+        match *self {
+            TooLarge           => write!(f, "HashLife universe would grow too large"),
+            GenerationOverflow => write!(f, "Generation count would overflow"),
+        }
+    }
+}
+
+impl error::Error for StepError {}
+
+/// Provides hosting for a HashLife simulation of a life-like ruleset on an unbounded plane.
+///
+/// Nodes and the memoised future of each node are cached between steps. When a step
+/// begins with more than the cache limit of nodes, which is about four million unless
+/// changed with `set_cache_limit`, the cache is cleared as by `clear_cache`.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u32), NodeId>,
+    empty: Vec<NodeId>,
+    /// Maps the 4x4 cells of a level 2 node to the 2x2 cells at its centre one generation later
+    table: Vec<u8>,
+    root: NodeId,
+    /// Coordinates of the top left cell of `root`
+    origin: (i64, i64),
+    gen: u64,
+    cache_limit: usize,
+}
+
+impl HashLife {
+    /// Constructs an empty universe which will be simulated with the given `rule`
+    ///
+    /// Panics if `rule` causes dead cells with no neighbours to be born (`B0`),
+    /// as the universe would no longer be mostly empty.
+    pub fn new(rule: LifeLike) -> HashLife {
+        assert!(!rule.is_birth(0), "HashLife does not support B0 rules");
+
+        let leaf = |population| Node { level: 0, children: [DEAD; 4], population };
+        let mut life = HashLife {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            table: make_table(rule),
+            root: DEAD,
+            origin: (0, 0),
+            gen: 0,
+            cache_limit: DEFAULT_CACHE_LIMIT,
+        };
+        life.root = life.empty(3);
+        life
+    }
+
+    /// Constructs a universe containing `grid`, with its top left cell at `(0, 0)`
+    pub fn from_grid(grid: &Grid, rule: LifeLike) -> HashLife {
+        let mut life = HashLife::new(rule);
+        let mut level = 3;
+        while (1 << level) < grid.width().max(grid.height()) {
+            level += 1;
+        }
+        life.root = life.build(grid, level, 0, 0);
+        life
+    }
+
+    /// Gets the number of generations this universe has been advanced by
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.gen
+    }
+
+    /// Gets the number of live cells in this universe
+    #[inline]
+    pub fn population(&self) -> u64 {
+        self.node(self.root).population
+    }
+
+    /// Advances the universe by `2^log2` generations
+    ///
+    /// Fails, leaving the universe at the same generation, if `log2` is greater than 59,
+    /// if the pattern would grow beyond the coordinates which fit in an `i64` or if the
+    /// generation count would overflow.
+    pub fn step_pow2(&mut self, log2: u32) -> Result<(), StepError> {
+        if log2 > MAX_STEP_LOG2 {
+            return Err(StepError::TooLarge);
+        }
+        let gen = self.gen.checked_add(1 << log2).ok_or(StepError::GenerationOverflow)?;
+        if self.nodes.len() > self.cache_limit {
+            self.clear_cache();
+        }
+
+        //Grow until the pattern sits within the centre half of a root large enough
+        //to be advanced by 2^log2 generations, then once more so that it cannot
+        //escape the centre half (which is all that `advance` returns)
+        while self.node(self.root).level < log2 + 2 || !self.is_padded() {
+            self.expand()?;
+        }
+        self.expand()?;
+
+        let level = self.node(self.root).level;
+        let quarter = 1i64 << (level - 2);
+        self.root = self.advance(self.root, log2);
+        self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+        self.gen = gen;
+        Ok(())
+    }
+
+    /// Advances the universe by `generations` generations
+    ///
+    /// Fails, leaving the universe unchanged, if `generations` is `2^60` or more or the
+    /// generation count would overflow. Also fails if the pattern would grow beyond the
+    /// coordinates which fit in an `i64`, in which case the universe is left at the last
+    /// generation reached.
+    pub fn step(&mut self, generations: u64) -> Result<(), StepError> {
+        if generations >> (MAX_STEP_LOG2 + 1) != 0 {
+            return Err(StepError::TooLarge);
+        }
+        self.gen.checked_add(generations).ok_or(StepError::GenerationOverflow)?;
+        for log2 in 0..=MAX_STEP_LOG2 {
+            if generations & (1 << log2) != 0 {
+                self.step_pow2(log2)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the number of nodes above which the cache is cleared before each step.
+    ///
+    /// The cache may exceed the limit during a single step, by as many nodes as that
+    /// step creates.
+    pub fn set_cache_limit(&mut self, nodes: usize) {
+        self.cache_limit = nodes;
+    }
+
+    /// Returns the number of nodes currently cached
+    pub fn cache_size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Discards the memoised future of every node, and every node which is not part of
+    /// the current pattern, releasing the memory they use
+    pub fn clear_cache(&mut self) {
+        let old = mem::take(&mut self.nodes);
+        self.nodes.extend_from_slice(&old[..2]);
+        self.index = HashMap::new();
+        self.results = HashMap::new();
+        self.empty.truncate(1);
+        let mut copied = HashMap::new();
+        self.root = self.copy(&old, self.root, &mut copied);
+    }
+
+    /// Returns the `width` by `height` region of the universe whose top left cell
+    /// is at `(x, y)` as a `Grid`
+    pub fn to_grid(&self, x: i64, y: i64, width: usize, height: usize) -> Grid {
+        let mut grid = Grid::create_dead(width, height);
+        let (ox, oy) = self.origin;
+        self.fill(&mut grid, self.root, ox - x, oy - y);
+        grid
+    }
+
+    #[inline]
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id.0 as usize]
+    }
+
+    /// Returns the canonical node with the given quadrants
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let level = self.node(children[0]).level + 1;
+        let population = children.iter().map(|&c| self.node(c).population).sum();
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(Node { level, children, population });
+        self.index.insert(children, id);
+        id
+    }
+
+    /// Rebuilds the node `id` of `old` as a canonical node, returning its new id
+    fn copy(&mut self, old: &[Node], id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        let node = old[id.0 as usize];
+        if node.level == 0 {
+            return id;
+        }
+        if let Some(&new) = copied.get(&id) {
+            return new;
+        }
+        let [nw, ne, sw, se] = node.children;
+        let children = [self.copy(old, nw, copied), self.copy(old, ne, copied),
+                        self.copy(old, sw, copied), self.copy(old, se, copied)];
+        let new = self.join(children);
+        copied.insert(id, new);
+        new
+    }
+
+    /// Returns the canonical empty node of the given level
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join([e; 4]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Builds the node of the given level whose top left cell is at `(x, y)` in `grid`
+    fn build(&mut self, grid: &Grid, level: u32, x: usize, y: usize) -> NodeId {
+        if x >= grid.width() || y >= grid.height() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if grid.cell_at(x, y).is_live() { LIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let nw = self.build(grid, level - 1, x, y);
+        let ne = self.build(grid, level - 1, x + half, y);
+        let sw = self.build(grid, level - 1, x, y + half);
+        let se = self.build(grid, level - 1, x + half, y + half);
+        self.join([nw, ne, sw, se])
+    }
+
+    /// Writes the live cells of `id`, whose top left cell is at `(x, y)`, into `grid`
+    fn fill(&self, grid: &mut Grid, id: NodeId, x: i64, y: i64) {
+        let node = self.node(id);
+        let size = 1i64 << node.level;
+        let outside =
+            x >= grid.width() as i64 || y >= grid.height() as i64 ||
+            x + size <= 0 || y + size <= 0;
+        if node.population == 0 || outside {
+            return;
+        }
+        if node.level == 0 {
+            grid.set_cell(x as usize, y as usize, Cell::Live);
+            return;
+        }
+        let half = size / 2;
+        let [nw, ne, sw, se] = node.children;
+        self.fill(grid, nw, x, y);
+        self.fill(grid, ne, x + half, y);
+        self.fill(grid, sw, x, y + half);
+        self.fill(grid, se, x + half, y + half);
+    }
+
+    /// Returns true if every live cell lies within the centre half of the root
+    fn is_padded(&self) -> bool {
+        let [nw, ne, sw, se] = self.node(self.root).children;
+        let [nw, ne, sw, se] = [self.node(nw), self.node(ne), self.node(sw), self.node(se)];
+        let border = [
+            nw.children[0], nw.children[1], nw.children[2],
+            ne.children[0], ne.children[1], ne.children[3],
+            sw.children[0], sw.children[2], sw.children[3],
+            se.children[1], se.children[2], se.children[3],
+        ];
+        border.iter().all(|&id| self.node(id).population == 0)
+    }
+
+    /// Doubles the size of the root, keeping the pattern centred
+    fn expand(&mut self) -> Result<(), StepError> {
+        let node = self.node(self.root);
+        if node.level >= MAX_LEVEL {
+            return Err(StepError::TooLarge);
+        }
+        let e = self.empty(node.level - 1);
+        let [nw, ne, sw, se] = node.children;
+        let nw = self.join([e, e, e, nw]);
+        let ne = self.join([e, e, ne, e]);
+        let sw = self.join([e, sw, e, e]);
+        let se = self.join([se, e, e, e]);
+        self.root = self.join([nw, ne, sw, se]);
+        let half = 1i64 << (node.level - 1);
+        self.origin = (self.origin.0 - half, self.origin.1 - half);
+        Ok(())
+    }
+
+    /// Returns the centre half of `id` (a node of level `k`)
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children;
+        self.join([
+            self.node(nw).children[3],
+            self.node(ne).children[2],
+            self.node(sw).children[1],
+            self.node(se).children[0],
+        ])
+    }
+
+    /// Returns the nine overlapping nodes of level `k - 1` which tile a node of level `k`
+    fn subnodes(&mut self, id: NodeId) -> [NodeId; 9] {
+        let [a, b, c, d] = self.node(id).children;
+        let [_, a1, a2, a3] = self.node(a).children;
+        let [b0, _, b2, b3] = self.node(b).children;
+        let [c0, c1, _, c3] = self.node(c).children;
+        let [d0, d1, d2, _] = self.node(d).children;
+        [
+            a,                          self.join([a1, b0, a3, b2]), b,
+            self.join([a2, a3, c0, c1]), self.join([a3, b2, c1, d0]), self.join([b2, b3, d0, d1]),
+            c,                          self.join([c1, d0, c3, d2]), d,
+        ]
+    }
+
+    /// Returns the centre half of `id` (a node of level `k`) advanced by `2^log2`
+    /// generations, where `log2 <= k - 2`
+    fn advance(&mut self, id: NodeId, log2: u32) -> NodeId {
+        let node = self.node(id);
+        debug_assert!(log2 + 2 <= node.level);
+
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, log2)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.advance_leaves(id)
+        }
+        else {
+            let s = self.subnodes(id);
+            let full_speed = log2 + 2 == node.level;
+
+            //Advance (or, when moving slower than full speed, take the centre of) each subnode
+            let mut r = [DEAD; 9];
+            for i in 0..9 {
+                r[i] = if full_speed { self.advance(s[i], log2 - 1) } else { self.centre(s[i]) };
+            }
+
+            let inner_log2 = if full_speed { log2 - 1 } else { log2 };
+            let mut quads = [DEAD; 4];
+            for (q, &(row, col)) in [(0, 0), (0, 1), (1, 0), (1, 1)].iter().enumerate() {
+                let i = row * 3 + col;
+                let joined = self.join([r[i], r[i + 1], r[i + 3], r[i + 4]]);
+                quads[q] = self.advance(joined, inner_log2);
+            }
+            self.join(quads)
+        };
+
+        self.results.insert((id, log2), result);
+        result
+    }
+
+    /// Advances a level 2 node by a single generation using the lookup table
+    fn advance_leaves(&mut self, id: NodeId) -> NodeId {
+        let mut bits = 0;
+        for (q, &quad) in self.node(id).children.iter().enumerate() {
+            for (c, &leaf) in self.node(quad).children.iter().enumerate() {
+                if leaf == LIVE {
+                    let x = (q & 1) * 2 + (c & 1);
+                    let y = (q >> 1) * 2 + (c >> 1);
+                    bits |= 1 << (y * 4 + x);
+                }
+            }
+        }
+        let result = self.table[bits];
+        let leaf = |i: usize| if result & (1 << i) != 0 { LIVE } else { DEAD };
+        self.join([leaf(0), leaf(1), leaf(2), leaf(3)])
+    }
+}
+
+/// Builds the table mapping every 4x4 block (bit `y * 4 + x`) to its 2x2 centre
+/// (bit `y * 2 + x`) one generation later
+fn make_table(rule: LifeLike) -> Vec<u8> {
+    use rules::Rule;
+
+    (0..1 << 16).map(|bits: usize| {
+        let live = |x: usize, y: usize| bits & (1 << (y * 4 + x)) != 0;
+        let mut result = 0;
+        for (i, &(cx, cy)) in [(1, 1), (2, 1), (1, 2), (2, 2)].iter().enumerate() {
+            let neighbours =
+                (cy - 1 .. cy + 2)
+                    .flat_map(|y| (cx - 1 .. cx + 2).map(move |x| (x, y)))
+                    .filter(|&(x, y)| (x, y) != (cx, cy) && live(x, y))
+                    .count();
+            let cell = if live(cx, cy) { Cell::Live } else { Cell::Dead };
+            if rule.next_cell(&cell, neighbours).is_some_and(|c| c.is_live()) {
+                result |= 1 << i;
+            }
+        }
+        result
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ HashLife, StepError };
+    use grid::Grid;
+    use grid::Cell::{ Live, Dead };
+    use rules::{ self, LifeLike };
+    use world::World;
+    use grid::tests as grid_test;
+
+    /// Places `pattern` at the centre of a dead grid with a margin wide enough that
+    /// it never touches the edge during the test
+    fn with_margin(pattern: &Grid, margin: usize) -> Grid {
+        let mut grid = Grid::create_dead(pattern.width() + margin * 2,
+                                         pattern.height() + margin * 2);
+        grid.write_cells(margin, margin, pattern);
+        grid
+    }
+
+    /// Steps `grid` with `World` using terminal neighbours and compares every
+    /// generation with the HashLife engine
+    fn cross_check(grid: &Grid, rule: LifeLike, generations: u64) {
+        let (w, h) = (grid.width(), grid.height());
+
+        for step in &[1, 2, 3, 5, 8] {
+            let mut life = HashLife::from_grid(grid, rule);
+            let mut world = World::new(grid.clone());
+            world.set_rules(rule).unwrap();
+            world.set_neighbours(rules::terminal_neighbours).unwrap();
+
+            let mut gen = 0;
+            while gen + step <= generations {
+                life.step(*step).unwrap();
+                for _ in 0..*step {
+                    world.step_mut();
+                }
+                gen += step;
+                assert_eq!(life.generation(), gen);
+                assert_eq!(&life.to_grid(0, 0, w, h), world.grid(), "Generation {}", gen);
+            }
+        }
+    }
+
+    #[test]
+    fn can_round_trip_grid() {
+        let grid = grid_test::make_glider_grid();
+        let life = HashLife::from_grid(&grid, LifeLike::conway());
+
+        assert_eq!(life.population(), 5);
+        assert_eq!(life.to_grid(0, 0, 6, 5), grid);
+        assert_eq!(life.to_grid(2, 1, 2, 2), Grid::from_raw(2, 2, vec![Dead, Live, Dead, Live]));
+        assert_eq!(life.to_grid(-10, -10, 3, 3), Grid::create_dead(3, 3));
+    }
+
+    #[test]
+    fn matches_world_for_glider() {
+        let grid = with_margin(&grid_test::make_glider_grid(), 12);
+        cross_check(&grid, LifeLike::conway(), 40);
+    }
+
+    #[test]
+    fn matches_world_for_r_pentomino() {
+        use grid::Cell::Dead as X;
+        use grid::Cell::Live as O;
+
+        let r_pentomino = Grid::from_raw(3, 3, vec![
+            X, O, O,
+            O, O, X,
+            X, O, X,
+        ]);
+        let grid = with_margin(&r_pentomino, 40);
+        cross_check(&grid, LifeLike::conway(), 60);
+    }
+
+    #[test]
+    fn matches_world_for_highlife_replicator() {
+        use grid::Cell::Dead as X;
+        use grid::Cell::Live as O;
+
+        let replicator = Grid::from_raw(5, 5, vec![
+            X, X, O, O, O,
+            X, O, X, X, O,
+            O, X, X, X, O,
+            O, X, X, O, X,
+            O, O, O, X, X,
+        ]);
+        let grid = with_margin(&replicator, 30);
+        cross_check(&grid, "B36/S23".parse().unwrap(), 40);
+    }
+
+    #[test]
+    fn can_advance_glider_by_huge_power_of_two() {
+        let glider = Grid::from_raw(3, 3, vec![
+            Dead, Live, Dead,
+            Dead, Dead, Live,
+            Live, Live, Live,
+        ]);
+        let mut life = HashLife::from_grid(&glider, LifeLike::conway());
+
+        //A glider travels one cell diagonally every four generations
+        life.step_pow2(40).unwrap();
+
+        let offset = 1i64 << 38;
+        assert_eq!(life.generation(), 1 << 40);
+        assert_eq!(life.population(), 5);
+        assert_eq!(life.to_grid(offset, offset, 3, 3), glider);
+    }
+
+    #[test]
+    fn can_advance_by_arbitrary_generations() {
+        let grid = with_margin(&grid_test::make_glider_grid(), 2);
+        let mut life = HashLife::from_grid(&grid, LifeLike::conway());
+
+        life.step(1_000_001).unwrap();
+
+        //After 1,000,000 generations the glider has travelled 250,000 cells
+        let mut expected = HashLife::from_grid(&grid, LifeLike::conway());
+        expected.step(1).unwrap();
+        let expected = expected.to_grid(0, 0, 10, 9);
+
+        assert_eq!(life.generation(), 1_000_001);
+        assert_eq!(life.to_grid(250_000, 250_000, 10, 9), expected);
+    }
+
+    #[test]
+    fn rejects_unsupported_step_sizes() {
+        let block = Grid::from_raw(2, 2, vec![Live; 4]);
+        let mut life = HashLife::from_grid(&block, LifeLike::conway());
+
+        assert_eq!(life.step_pow2(63), Err(StepError::TooLarge));
+        assert_eq!(life.step(1 << 63), Err(StepError::TooLarge));
+        assert_eq!(life.step(u64::MAX), Err(StepError::TooLarge));
+        assert_eq!(life.generation(), 0);
+
+        //A still life can be advanced until the generation count would overflow
+        for _ in 0..16 {
+            life.step((1 << 60) - 1).unwrap();
+        }
+        assert_eq!(life.step(16), Err(StepError::GenerationOverflow));
+        assert_eq!(life.generation(), u64::MAX - 15);
+        assert_eq!(life.population(), 4);
+
+        //A glider eventually leaves the range of coordinates
+        let mut life = HashLife::from_grid(&grid_test::make_glider_grid(), LifeLike::conway());
+        let mut steps = 0;
+        while life.step_pow2(59).is_ok() {
+            steps += 1;
+            assert!(steps < 16, "Glider was advanced too far");
+        }
+        assert_eq!(life.step_pow2(59), Err(StepError::TooLarge));
+        assert_eq!(life.generation(), steps << 59);
+        assert_eq!(life.population(), 5);
+    }
+
+    #[test]
+    fn can_clear_cache() {
+        let grid = with_margin(&grid_test::make_glider_grid(), 12);
+        let mut life = HashLife::from_grid(&grid, LifeLike::conway());
+        let mut limited = HashLife::from_grid(&grid, LifeLike::conway());
+        limited.set_cache_limit(0);
+
+        for _ in 0..20 {
+            life.step(3).unwrap();
+            limited.step(3).unwrap();
+            assert_eq!(limited.to_grid(0, 0, 29, 29), life.to_grid(0, 0, 29, 29));
+        }
+        assert!(limited.cache_size() < life.cache_size());
+
+        let size = life.cache_size();
+        life.clear_cache();
+        assert!(life.cache_size() < size);
+        assert_eq!(life.population(), 5);
+        assert_eq!(life.to_grid(0, 0, 29, 29), limited.to_grid(0, 0, 29, 29));
+    }
+
+    #[test]
+    #[should_panic(expected = "B0")]
+    fn b0_rules_panic() {
+        HashLife::new("B0/S23".parse().unwrap());
+    }
+}
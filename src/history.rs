@@ -0,0 +1,116 @@
+//! A bounded history of previous grid states, used to step a `World` backwards.
+
+use std::collections::VecDeque;
+
+use grid::{ Grid, Cell };
+
+/// Selects how a `World` stores the states in its history
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HistoryMode {
+    /// Stores a complete copy of the `Grid` for each state
+    Full,
+    /// Stores only the cells which changed between each state and the next, so memory
+    /// use is proportional to the number of changed cells
+    Diffs,
+}
+
+/// A previous state, stored relative to the state which followed it
+#[derive(Clone)]
+enum Snapshot {
+    Full(Grid),
+    /// The index and previous value of each cell which changed
+    Diff(Vec<(usize, Cell)>),
+}
+
+#[derive(Clone)]
+struct Entry {
+    gen: i64,
+    snapshot: Snapshot,
+}
+
+/// A ring of at most `depth` previous states, newest last
+#[derive(Clone)]
+pub struct History {
+    depth: usize,
+    mode: HistoryMode,
+    entries: VecDeque<Entry>,
+    /// The state before any edits made at the current generation, which have not yet
+    /// been recorded
+    edit: Option<Grid>,
+}
+
+impl History {
+    pub fn new(depth: usize, mode: HistoryMode) -> History {
+        History { depth, mode, entries: VecDeque::new(), edit: None }
+    }
+
+    /// Returns the generation of each state which can be restored, oldest first, where
+    /// `current` is the state at generation `gen`. Pending edits which left the state
+    /// unchanged are not counted.
+    pub fn generations(&self, gen: i64, current: &Grid) -> Vec<i64> {
+        let mut gens: Vec<i64> = self.entries.iter().map(|e| e.gen).collect();
+        if self.edit.as_ref().is_some_and(|before| before != current) {
+            gens.push(gen);
+        }
+        gens
+    }
+
+    /// Notes that `current` is about to be edited. Only the state before the first edit
+    /// at each generation is kept, so a group of edits is undone together.
+    pub fn begin_edit(&mut self, current: &Grid) {
+        if self.edit.is_none() {
+            self.edit = Some(current.clone());
+        }
+    }
+
+    /// Records any pending edits made to `current` at generation `gen`
+    pub fn settle(&mut self, gen: i64, current: &Grid) {
+        if let Some(before) = self.edit.take() {
+            if &before != current {
+                self.push(gen, &before, current);
+            }
+        }
+    }
+
+    /// Records `before`, the state at generation `gen`, which was followed by `after`
+    pub fn push(&mut self, gen: i64, before: &Grid, after: &Grid) {
+        if self.depth == 0 {
+            return;
+        }
+        let same_size = before.width() == after.width() && before.height() == after.height();
+        let snapshot = match self.mode {
+            HistoryMode::Diffs if same_size => {
+                let changes = before.iter_cells()
+                    .zip(after.iter_cells())
+                    .filter(|&((_, _, old), (_, _, new))| old != new)
+                    .map(|((x, y, old), _)| (y * before.width() + x, old.clone()))
+                    .collect();
+                Snapshot::Diff(changes)
+            },
+            _ => Snapshot::Full(before.clone()),
+        };
+        if self.entries.len() == self.depth {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry { gen, snapshot });
+    }
+
+    /// Restores the newest state into `current`, which must hold the state that followed
+    /// it, and returns its generation. Returns `None` if there are no states.
+    ///
+    /// Pending edits are recorded first, unless they left `current` unchanged.
+    pub fn pop(&mut self, gen: i64, current: &mut Grid) -> Option<i64> {
+        self.settle(gen, current);
+        let entry = self.entries.pop_back()?;
+        match entry.snapshot {
+            Snapshot::Full(grid) => *current = grid,
+            Snapshot::Diff(changes) => {
+                let cells = current.cells_mut();
+                for (i, cell) in changes {
+                    cells[i] = cell;
+                }
+            },
+        }
+        Some(entry.gen)
+    }
+}
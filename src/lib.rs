@@ -9,6 +9,17 @@
 extern crate test;
 
 pub mod plaintext;
+pub mod rle;
 pub mod rules;
 pub mod world;
 pub mod grid;
+pub mod bitgrid;
+pub mod hashlife;
+pub mod universe;
+pub mod period;
+pub mod stats;
+pub mod census;
+pub mod apgcode;
+
+mod bands;
+mod history;
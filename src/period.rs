@@ -0,0 +1,431 @@
+//! Functions used for detecting still lifes, oscillators, spaceships and other repeating
+//! patterns.
+//!
+//! A `World` with a finite `Grid` must eventually repeat a previous state. A 64-bit hash
+//! of each state is kept in a hash map as the world is stepped. When a hash repeats, the
+//! earlier state is re-simulated and compared in full, including any `Dying` cells, so
+//! only one copy of the world is kept however long the search runs.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+
+use grid::Grid;
+use rules::Edges;
+use universe::Universe;
+use world::World;
+
+/// The number of generations `simulate` runs each object for while finding its period
+const MAX_PERIOD: i64 = 256;
+
+/// A cycle of repeating states found by `find_cycle`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Cycle {
+    /// The generation at which the cycle began, i.e. the first generation holding `state`
+    pub start: i64,
+    /// The number of generations after which each state in the cycle repeats
+    pub period: i64,
+    /// The first state to be repeated
+    pub state: Grid,
+}
+
+impl Cycle {
+    /// Returns true if every cell in the cycle is dead
+    pub fn is_dead(&self) -> bool {
+        self.state.iter_cells().all(|(_, _, cell)| cell.is_dead())
+    }
+
+    /// Returns true if the cycle is a single unchanging state with live cells
+    pub fn is_still(&self) -> bool {
+        self.period == 1 && !self.is_dead()
+    }
+
+    /// Returns true if the cycle passes through more than one state
+    pub fn is_oscillator(&self) -> bool {
+        self.period > 1
+    }
+}
+
+/// Steps `world` in place until it returns to a state it held previously, stepping at
+/// most `limit` generations.
+///
+/// Returns the cycle found, or `None` if no state repeated within `limit` generations.
+/// On success `world` is left at generation `start + period`, the first repeat.
+pub fn find_cycle(world: &mut World, limit: usize) -> Option<Cycle> {
+    let origin = world.fork();
+    let mut seen: HashMap<u64, Vec<i64>> = HashMap::new();
+    seen.insert(hash_of(world.grid()), vec![world.generation()]);
+
+    for _ in 0..limit {
+        world.step_mut();
+        let gen = world.generation();
+        let gens = seen.entry(hash_of(world.grid())).or_default();
+        if let Some(&start) = gens.iter().find(|&&g| replay(&origin, g).grid() == world.grid()) {
+            return Some(Cycle { start, period: gen - start, state: world.grid().clone() });
+        }
+        gens.push(gen);
+    }
+
+    None
+}
+
+/// A pattern which returns to the same shape, possibly translated, found by `find_translation`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Translation {
+    /// The generation at which the repeated shape first appeared
+    pub start: i64,
+    /// The number of generations after which the shape repeats
+    pub period: i64,
+    /// The horizontal displacement of the shape after each period
+    pub dx: i64,
+    /// The vertical displacement of the shape after each period
+    pub dy: i64,
+}
+
+impl Translation {
+    /// Returns true if the shape moves, i.e. the pattern is a spaceship
+    pub fn is_spaceship(&self) -> bool {
+        (self.dx, self.dy) != (0, 0)
+    }
+
+    /// Returns the speed of the shape in `c` notation, e.g. `c/4 diagonal`
+    pub fn speed(&self) -> Speed {
+        Speed { dx: self.dx.abs(), dy: self.dy.abs(), period: self.period }
+    }
+}
+
+/// The speed of a `Translation`, displayed in `c` notation
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Speed {
+    dx: i64,
+    dy: i64,
+    period: i64,
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (major, minor) = (self.dx.max(self.dy), self.dx.min(self.dy));
+        if major == 0 {
+            return write!(f, "0");
+        }
+        if minor != 0 && minor != major {
+            return write!(f, "({},{})c/{}", major, minor, self.period);
+        }
+        let divisor = gcd(major, self.period);
+        match (major / divisor, self.period / divisor) {
+            (1, 1) => write!(f, "c")?,
+            (n, 1) => write!(f, "{}c", n)?,
+            (1, d) => write!(f, "c/{}", d)?,
+            (n, d) => write!(f, "{}c/{}", n, d)?,
+        }
+        write!(f, " {}", if minor == 0 { "orthogonal" } else { "diagonal" })
+    }
+}
+
+/// Steps `world` in place until it returns to a shape it held previously, allowing for
+/// translation, stepping at most `limit` generations.
+///
+/// Each state is normalised to the bounding box of its live and dying cells. When the world is
+/// a torus the bounding box may wrap around the edges of the grid, and displacements
+/// are reported as the shortest distance around the torus.
+///
+/// Returns `None` if no shape repeated within `limit` generations. On success `world`
+/// is left at generation `start + period`, the first repeat.
+pub fn find_translation(world: &mut World, limit: usize) -> Option<Translation> {
+    let torus = world.edges() == Some(Edges::Torus);
+    let origin = world.fork();
+    let mut seen: HashMap<u64, Vec<(i64, i64, i64)>> = HashMap::new();
+    let (shape, x, y) = normalise(world.grid(), torus);
+    seen.insert(hash_of(&shape), vec![(world.generation(), x, y)]);
+
+    for _ in 0..limit {
+        world.step_mut();
+        let gen = world.generation();
+        let (shape, x, y) = normalise(world.grid(), torus);
+        let states = seen.entry(hash_of(&shape)).or_default();
+        let repeat = states.iter().find(|&&(g, _, _)| {
+            normalise(replay(&origin, g).grid(), torus).0 == shape
+        });
+        if let Some(&(start, x0, y0)) = repeat {
+            let (mut dx, mut dy) = (x - x0, y - y0);
+            if torus {
+                dx = shortest(dx, world.width() as i64);
+                dy = shortest(dy, world.height() as i64);
+            }
+            return Some(Translation { start, period: gen - start, dx, dy });
+        }
+        states.push((gen, x, y));
+    }
+
+    None
+}
+
+/// Describes how an object behaves when simulated on its own
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Kind {
+    /// The object never changes
+    Still,
+    /// The object returns to its starting state after `period` generations
+    Oscillator { period: i64 },
+    /// The object returns to its starting shape after `period` generations, moved by `(dx, dy)`
+    Spaceship { period: i64, dx: i64, dy: i64 },
+    /// The object does not return to its starting shape within the simulated generations,
+    /// for example because it is still evolving or dies
+    Unstable,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Kind::Still                 => write!(f, "still life"),
+            Kind::Oscillator { period } => write!(f, "p{} oscillator", period),
+            Kind::Spaceship { period, dx, dy } => {
+                write!(f, "{} spaceship", Translation { start: 0, period, dx, dy }.speed())
+            },
+            Kind::Unstable              => write!(f, "unstable object"),
+        }
+    }
+}
+
+/// Simulates `cells` on its own under the standard rules, returning its kind along with
+/// each of its phases cropped to their live cells. Unstable objects have a single phase.
+pub(crate) fn simulate(cells: &Grid) -> (Kind, Vec<Grid>) {
+    let mut universe = Universe::from_grid(cells);
+    let mut seen: HashMap<u64, Vec<(i64, i64, i64)>> = HashMap::new();
+    let mut phases: Vec<Grid> = Vec::new();
+
+    for gen in 0..MAX_PERIOD + 1 {
+        let (x, y, w, h) = universe.bounds().unwrap_or((0, 0, 0, 0));
+        let shape = universe.to_grid(x, y, w, h);
+        let states = seen.entry(hash_of(&shape)).or_default();
+        // Every shape is kept as a phase, so a repeated hash is checked against it
+        let repeat = states.iter().find(|&&(g, _, _)| phases[g as usize] == shape);
+        if let Some(&(start, x0, y0)) = repeat {
+            if start != 0 || shape.population() == 0 {
+                break;
+            }
+            let kind = match (gen, x - x0, y - y0) {
+                (1, _, _)        => Kind::Still,
+                (period, 0, 0)   => Kind::Oscillator { period },
+                (period, dx, dy) => Kind::Spaceship { period, dx, dy },
+            };
+            return (kind, phases);
+        }
+        states.push((gen, x, y));
+        phases.push(shape);
+        universe.step_mut();
+    }
+
+    (Kind::Unstable, vec![cells.clone()])
+}
+
+/// Returns a 64-bit hash of `value`, used in place of a full copy to key each state seen
+fn hash_of<T>(value: &T) -> u64
+    where T: Hash + ?Sized
+{
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns a copy of `origin` stepped forward to generation `gen`
+fn replay(origin: &World, gen: i64) -> World {
+    let mut world = origin.fork();
+    while world.generation() < gen {
+        world.step_mut();
+    }
+    world
+}
+
+/// Returns the live and dying cells of `grid` cropped to their bounding box, along with the position
+/// of the box. If `torus` is set the box may wrap around the edges of the grid.
+fn normalise(grid: &Grid, torus: bool) -> (Grid, i64, i64) {
+    let (x, y, w, h) = if torus {
+        let (x, w) = wrapped_span(grid.width(), |x| (0..grid.height()).any(|y| !grid.cell_at(x, y).is_dead()));
+        let (y, h) = wrapped_span(grid.height(), |y| (0..grid.width()).any(|x| !grid.cell_at(x, y).is_dead()));
+        (x, y, w, h)
+    }
+    else {
+        grid.occupied_bounds().unwrap_or((0, 0, 0, 0))
+    };
+    let shape = Grid::from_fn(w, h, |sx, sy| {
+        grid.cell_at((x + sx) % grid.width(), (y + sy) % grid.height()).clone()
+    });
+    (shape, x as i64, y as i64)
+}
+
+/// Returns the start and length of the shortest span of `len` indices, wrapping around,
+/// which contains every index for which `occupied` returns true
+fn wrapped_span<F>(len: usize, occupied: F) -> (usize, usize)
+    where F: Fn(usize) -> bool
+{
+    let occupied: Vec<bool> = (0..len).map(occupied).collect();
+    if !occupied.iter().any(|&o| o) {
+        return (0, 0);
+    }
+    // Find the longest run of unoccupied indices, wrapping around; the span starts after it
+    let (mut best_end, mut best_len, mut run) = (0, 0, 0);
+    for i in 0..len * 2 {
+        if occupied[i % len] {
+            run = 0;
+        }
+        else {
+            run += 1;
+            if run > best_len && run <= len {
+                best_end = i % len;
+                best_len = run;
+            }
+        }
+    }
+    ((best_end + 1) % len, len - best_len)
+}
+
+/// Returns `delta` wrapped into the shortest signed distance around a dimension of `len`
+fn shortest(delta: i64, len: i64) -> i64 {
+    let delta = delta.rem_euclid(len);
+    if delta * 2 > len { delta - len } else { delta }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ find_cycle, find_translation, normalise, Translation };
+    use world::World;
+    use rules;
+    use grid::{ Grid, Cell };
+    use grid::tests as grid_test;
+    use grid::tests::{ make_grid, make_blinker_grid, make_toad_grid, make_pulsar_grid, make_lwss_grid };
+
+    #[test]
+    fn can_find_period_of_oscillators() {
+        for &(ref grid, period) in &[(make_blinker_grid(), 2),
+                                     (make_toad_grid(), 2),
+                                     (make_pulsar_grid(), 3)] {
+            let mut world = World::new(grid.clone());
+            let cycle = find_cycle(&mut world, 100).unwrap();
+
+            assert_eq!(cycle.start, 0);
+            assert_eq!(cycle.period, period);
+            assert_eq!(&cycle.state, grid);
+            assert!(cycle.is_oscillator());
+            assert_eq!(world.generation(), period);
+        }
+    }
+
+    #[test]
+    fn can_find_cycle_from_later_generation() {
+        let mut world = World::new(make_blinker_grid());
+        world.step_mut();
+        world.step_mut();
+        world.step_mut();
+        let cycle = find_cycle(&mut world, 10).unwrap();
+
+        assert_eq!((cycle.start, cycle.period), (3, 2));
+        assert_eq!(world.generation(), 5);
+    }
+
+    #[test]
+    fn can_find_still_life() {
+        let mut world = World::new(make_grid(&["....",
+                                                ".OO.",
+                                                ".OO.",
+                                                "...."]));
+        let cycle = find_cycle(&mut world, 10).unwrap();
+
+        assert_eq!((cycle.start, cycle.period), (0, 1));
+        assert!(cycle.is_still());
+    }
+
+    #[test]
+    fn can_find_death() {
+        let mut world = World::new(grid_test::make_lonely_grid());
+        let cycle = find_cycle(&mut world, 10).unwrap();
+
+        assert_eq!((cycle.start, cycle.period), (1, 1));
+        assert!(cycle.is_dead());
+        assert!(!cycle.is_still());
+    }
+
+    #[test]
+    fn glider_on_torus_returns_to_start() {
+        let grid = grid_test::make_glider_grid();
+        let mut world = World::new(grid.clone());
+
+        // The glider moves (1, 1) every 4 generations, so must cross the 6x5 torus
+        // 5 times horizontally and 6 times vertically to return to its start
+        let cycle = find_cycle(&mut world, 1000).unwrap();
+
+        assert_eq!((cycle.start, cycle.period), (0, 120));
+        assert_eq!(cycle.state, grid);
+    }
+
+    #[test]
+    fn gives_up_after_limit() {
+        let mut world = World::new(grid_test::make_glider_grid());
+
+        assert_eq!(find_cycle(&mut world, 119), None);
+        assert_eq!(world.generation(), 119);
+    }
+
+    #[test]
+    fn can_find_glider_speed() {
+        let mut world = World::new(grid_test::make_glider_grid());
+        let t = find_translation(&mut world, 100).unwrap();
+
+        assert_eq!(t, Translation { start: 0, period: 4, dx: 1, dy: 1 });
+        assert!(t.is_spaceship());
+        assert_eq!(t.speed().to_string(), "c/4 diagonal");
+    }
+
+    #[test]
+    fn can_find_spaceship_across_torus_edges() {
+        // Place a lightweight spaceship so that it wraps around every edge of the grid
+        let lwss = make_lwss_grid();
+        let grid = Grid::from_fn(12, 8, |x, y| {
+            let (lx, ly) = ((x + 2) % 12, (y + 2) % 8);
+            if lx < 5 && ly < 4 { lwss.cell_at(lx, ly).clone() } else { Cell::Dead }
+        });
+        let mut world = World::new(grid);
+        let t = find_translation(&mut world, 100).unwrap();
+
+        assert_eq!(t, Translation { start: 0, period: 4, dx: -2, dy: 0 });
+        assert_eq!(t.speed().to_string(), "c/2 orthogonal");
+    }
+
+    #[test]
+    fn oscillators_do_not_move() {
+        let mut world = World::new(make_pulsar_grid());
+        world.set_neighbours(rules::TerminalNeighbours).unwrap();
+        let t = find_translation(&mut world, 100).unwrap();
+
+        assert_eq!(t, Translation { start: 0, period: 3, dx: 0, dy: 0 });
+        assert!(!t.is_spaceship());
+        assert_eq!(t.speed().to_string(), "0");
+    }
+
+    #[test]
+    fn can_format_speeds() {
+        let speed = |dx, dy, period| Translation { start: 0, period, dx, dy }.speed().to_string();
+
+        assert_eq!(speed(1, 0, 1), "c orthogonal");
+        assert_eq!(speed(0, -2, 4), "c/2 orthogonal");
+        assert_eq!(speed(2, 0, 5), "2c/5 orthogonal");
+        assert_eq!(speed(-1, 1, 4), "c/4 diagonal");
+        assert_eq!(speed(2, 1, 6), "(2,1)c/6");
+    }
+
+    #[test]
+    fn normalised_shapes_include_dying_cells() {
+        use grid::Cell::{ Live, Dead, Dying };
+
+        let grid = Grid::from_raw(5, 1, vec![Dying(2), Dead, Live, Dying(3), Dead]);
+
+        assert_eq!(normalise(&grid, false), (Grid::from_raw(4, 1, vec![Dying(2), Dead, Live, Dying(3)]), 0, 0));
+        assert_eq!(normalise(&grid, true), (Grid::from_raw(4, 1, vec![Live, Dying(3), Dead, Dying(2)]), 2, 0));
+    }
+}
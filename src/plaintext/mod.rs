@@ -1,19 +1,18 @@
-//! Module for parsing the [PlainText](http://conwaylife.com/wiki/PlainText) Game of Life
-//! file format into a `Grid`.
+//! Module for parsing and writing the [PlainText](http://conwaylife.com/wiki/PlainText)
+//! Game of Life file format.
 
-mod padding;
+pub mod padding;
 
 use grid::{ Cell, Grid };
 use grid::Cell::*;
 
-use self::padding::Padding;
+pub use self::padding::Padding;
 
 use std::vec::Vec;
 use std::result;
 use std::io;
 use std::fmt;
 use std::convert;
-use std::iter;
 
 /// Struct for the contents of a PlainText format Game of Life file.
 ///
@@ -36,6 +35,12 @@ use std::iter;
 /// |-----|-------|--------|------|
 /// | 5   | 10    | 5      | 10   |
 ///
+/// # Dying cells
+///
+/// Cells of rules with more than two states are written as lowercase letters, with `b`
+/// for state 2 through to `x` for state 24, matching the multi-state letters of RLE.
+///
+#[derive(PartialEq, Debug)]
 pub struct PlainText {
     pub name: String,
     pub comment: String,
@@ -129,6 +134,7 @@ pub fn parse_plaintext<R>(reader: R) -> ParseResult
                 match c {
                     'O' => row.push(Live),
                     '.' => row.push(Dead),
+                    'b' ..= 'x' => row.push(Dying(c as u8 - b'a' + 1)),
                      _  => return Err(ParseError::Invalid),
                 }
             }
@@ -152,22 +158,70 @@ pub fn parse_plaintext<R>(reader: R) -> ParseResult
 }
 
 fn pad_and_create_grid(rows: Vec<Vec<Cell>>, width: usize, p: Padding) -> Grid {
+    let height = rows.len();
+    let cells = rows.into_iter().flatten().collect();
+    Grid::from_raw(width, height, cells).pad(p)
+}
 
-    let width = width + p.left + p.right;
-    let height = rows.len() + p.top + p.bottom;
-
-    let mut cells = Vec::with_capacity(width * height);
-    let dead_cells = |c| iter::repeat(Dead).take(c);
+/// The highest dying state which can be written as a letter
+const MAX_STATE: u8 = 24;
 
-    cells.extend(dead_cells(p.top * width));
-    for row in rows {
-        cells.extend(dead_cells(p.left));
-        cells.extend(row);
-        cells.extend(dead_cells(p.right));
+/// Writes the [PlainText](http://conwaylife.com/wiki/PlainText) format to a stream
+///
+/// Dead margins around the live cells are written using the `!Padding:` extension, so
+/// that `parse_plaintext` reproduces the original `Grid`. Note that the name and each
+/// comment line are trimmed when parsed, and leading blank comment lines are dropped.
+///
+/// Fails with `InvalidInput` if the `Grid` contains dying cells beyond state 24.
+pub fn write_plaintext<W>(mut writer: W, plaintext: &PlainText) -> io::Result<()>
+    where W: io::Write
+{
+    let grid = &plaintext.data;
+    let (w, h) = (grid.width(), grid.height());
+
+    let (padding, x, width) = match grid.occupied_bounds() {
+        Some((x, y, width, height)) => {
+            (Padding::new(y, w - x - width, h - y - height, x), x, width)
+        },
+        //No body rows, so the padding alone describes the grid
+        None => (Padding::new(h, w, 0, 0), 0, 0),
+    };
+
+    writeln!(writer, "!Name: {}", plaintext.name)?;
+    if padding != Padding::new(0, 0, 0, 0) {
+        writeln!(writer, "!Padding: {}", padding)?;
     }
-    cells.extend(dead_cells(p.bottom * width));
-
-    Grid::from_raw(width, height, cells)
+    if !plaintext.comment.is_empty() {
+        for line in plaintext.comment.split('\n') {
+            if line.is_empty() {
+                writeln!(writer, "!")?;
+            }
+            else {
+                writeln!(writer, "! {}", line)?;
+            }
+        }
+    }
+    if width == 0 {
+        return Ok(());
+    }
+    for row in grid.iter_rows().skip(padding.top).take(h - padding.top - padding.bottom) {
+        let line = row[x .. x + width]
+            .iter()
+            .map(|c| {
+                match *c {
+                    Live                      => Ok('O'),
+                    Dead                      => Ok('.'),
+                    Dying(n) if n <= MAX_STATE => Ok((b'a' + n - 1) as char),
+                    Dying(n) => {
+                        let message = format!("State {} has no PlainText letter", n);
+                        Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+                    },
+                }
+            })
+            .collect::<io::Result<String>>()?;
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -312,4 +366,120 @@ O.
 
         assert!(!result.is_ok(), "Result is Ok");
     }
+
+    fn round_trip(value: &super::PlainText) -> (String, super::PlainText) {
+        let mut bytes = Vec::new();
+        super::write_plaintext(&mut bytes, value).unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        let parsed = super::parse_plaintext(io::Cursor::new(bytes)).unwrap();
+        (text, parsed)
+    }
+
+    #[test]
+    fn can_write_simple_plaintext() {
+        use grid::Grid;
+
+        let value = super::PlainText {
+            name: "Tumbler".to_string(),
+            comment: "This is a comment\n\nSecond paragraph".to_string(),
+            data: Grid::from_raw(2, 2, vec![Dead, Live, Live, Dead]),
+        };
+
+        let (text, parsed) = round_trip(&value);
+
+        assert_eq!(text, "!Name: Tumbler
+! This is a comment
+!
+! Second paragraph
+.O
+O.
+");
+        assert_eq!(parsed, value);
+    }
+
+    #[test]
+    fn can_write_padding_extension() {
+        use grid::Grid;
+
+        let mut grid = Grid::create_dead(6, 4);
+        grid.set_cell(3, 1, Live);
+        grid.set_cell(2, 2, Live);
+
+        let value = super::PlainText {
+            name: "Tumbler".to_string(),
+            comment: "This is a comment\n".to_string(),
+            data: grid,
+        };
+
+        let (text, parsed) = round_trip(&value);
+
+        assert_eq!(text, "!Name: Tumbler
+!Padding: 1,2,1,2
+! This is a comment
+!
+.O
+O.
+");
+        assert_eq!(parsed, value);
+    }
+
+    #[test]
+    fn can_round_trip_grids() {
+        use grid::{ Grid, Cell };
+        use grid::tests::{ make_square_grid,
+                           make_pipe_grid,
+                           make_lonely_grid,
+                           make_oblong_grid,
+                           make_glider_grid };
+
+        let grids = vec![
+            make_square_grid(),
+            make_pipe_grid(),
+            make_lonely_grid(),
+            make_oblong_grid(),
+            make_glider_grid(),
+            Grid::create_dead(5, 3),
+            Grid::create_dead(0, 0),
+            Grid::from_fn(3, 2, |x, y| Cell::from_state((y * 3 + x) as u8 * 4)),
+        ];
+
+        for grid in grids {
+            let value = super::PlainText {
+                name: "Round trip".to_string(),
+                comment: String::new(),
+                data: grid,
+            };
+
+            let (_, parsed) = round_trip(&value);
+
+            assert_eq!(parsed, value);
+        }
+    }
+
+    #[test]
+    fn can_parse_dying_cells() {
+        use grid::Cell::Dying;
+
+        const PLAINTEXT: &str = "!Name: Brian's Brain\n.Ob\nOcx";
+
+        let cursor = io::Cursor::new(PLAINTEXT.to_string().into_bytes());
+        let result = super::parse_plaintext(io::BufReader::new(cursor)).unwrap();
+
+        assert_eq!(result.data.iter_cells().map(|(_, _, c)| c.clone()).collect::<Vec<_>>(),
+                   vec![Dead, Live, Dying(2), Live, Dying(3), Dying(24)]);
+    }
+
+    #[test]
+    fn write_fails_with_unrepresentable_states() {
+        use grid::{ Grid, Cell };
+
+        let value = super::PlainText {
+            name: String::new(),
+            comment: String::new(),
+            data: Grid::from_fn(1, 1, |_, _| Cell::Dying(25)),
+        };
+
+        let err = super::write_plaintext(Vec::new(), &value).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
 }
@@ -7,7 +7,7 @@ use std::num;
 
 
 /// Describes padding in the order `top`, `right`, `bottom`, `left`
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Padding {
     pub top: usize,
     pub right: usize,
@@ -22,6 +22,13 @@ impl Padding {
     }
 }
 
+impl fmt::Display for Padding {
+    /// Formats the padding as a `top,right,bottom,left` expression
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.top, self.right, self.bottom, self.left)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ParseError {
     TooManyParts,
@@ -120,6 +127,13 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_round_trip_display() {
+        let padding = Padding::new(1, 2, 3, 4);
+        assert_eq!(padding.to_string(), "1,2,3,4");
+        assert_eq!(Ok(Padding::new(1, 2, 3, 4)), padding.to_string().parse());
+    }
+
     #[test]
     fn fails_with_more_than_five_values() {
         let expected: ParseResult = Err(ParseError::TooManyParts);
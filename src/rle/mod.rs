@@ -0,0 +1,687 @@
+//! Module for parsing and writing the [RLE](http://conwaylife.com/wiki/Run_Length_Encoded)
+//! Game of Life file format.
+
+use grid::{ Cell, Grid };
+
+use std::result;
+use std::io;
+use std::fmt;
+use std::convert;
+
+/// Struct for the contents of an RLE format Game of Life file.
+///
+/// # Example
+///
+/// ```text
+/// #N Glider
+/// #O Richard K. Guy
+/// #C The smallest, most common, and first discovered spaceship.
+/// x = 3, y = 3, rule = B3/S23
+/// bob$2bo$3o!
+/// ```
+///
+/// The `#N`, `#C` (or `#c`), `#O` and `#P` (or `#R`) lines populate `name`, `comment`,
+/// `author` and `offset` respectively. Multiple comment lines are joined with `\n`.
+#[derive(Debug)]
+pub struct Rle {
+    pub name: String,
+    pub comment: String,
+    pub author: String,
+    pub rule: Option<String>,
+    pub offset: Option<(i64, i64)>,
+    pub data: Grid
+}
+
+impl Rle {
+    /// Constructs a new `Rle` for the given `Grid` with no metadata
+    pub fn new(data: Grid) -> Rle {
+        Rle {
+            name: String::new(),
+            comment: String::new(),
+            author: String::new(),
+            rule: None,
+            offset: None,
+            data
+        }
+    }
+}
+
+/// Represents any errors which occur during the RLE parsing process
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    HeaderMissing,
+    InvalidHeader,
+    OutOfBounds,
+    Invalid
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use self::ParseError::*;
+        match *self {
+            Io(ref e) => write!(fmt, "I/O Error: {}", e),
+            HeaderMissing => write!(fmt, "Header line missing"),
+            InvalidHeader => write!(fmt, "Header line was not a valid `x = m, y = n` expression"),
+            OutOfBounds => write!(fmt, "Body contained cells outside of the declared size"),
+            Invalid => write!(fmt, "Body contained invalid data"),
+        }
+    }
+}
+
+impl convert::From<io::Error> for ParseError {
+    fn from(err: io::Error) -> ParseError {
+        ParseError::Io(err)
+    }
+}
+
+/// Represents the result of an RLE parse operation
+pub type ParseResult = result::Result<Rle, ParseError>;
+
+/// The values declared by an RLE header line
+struct Header {
+    width: usize,
+    height: usize,
+    rule: Option<String>,
+}
+
+/// Parses a `x = m, y = n[, rule = abc]` header line
+///
+/// The rule is the rest of the line after `rule =`, as rulestrings such as those of
+/// Larger than Life rules may themselves contain commas.
+fn parse_header(line: &str) -> Option<Header> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+
+    let (sizes, rest) = match line.find("rule") {
+        Some(i) => line.split_at(i),
+        None    => (line, ""),
+    };
+    if !rest.is_empty() {
+        let value = rest["rule".len()..].trim_start().strip_prefix('=')?;
+        rule = Some(value.trim().to_string());
+    }
+
+    for part in sizes.split(',').filter(|part| !part.trim().is_empty()) {
+        let mut kv = part.splitn(2, '=');
+        let key = kv.next().unwrap_or("").trim();
+        let value = kv.next()?.trim();
+        match key {
+            "x"    => width = value.parse().ok(),
+            "y"    => height = value.parse().ok(),
+             _     => {}, //Ignore unknown keys
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Some(Header { width, height, rule }),
+        _                           => None,
+    }
+}
+
+/// Parses the `x y` coordinates of a `#P` or `#R` line
+fn parse_offset(s: &str) -> Option<(i64, i64)> {
+    let mut parts = s.split_whitespace().map(|p| p.parse());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some((x, y)),
+        _                                => None,
+    }
+}
+
+/// The number of states described by each multi-state letter prefix
+const STATES_PER_PREFIX: usize = 24;
+
+/// Returns the cell described by a multi-state `letter`, from `A` to `X`, and an
+/// optional `prefix`, from `p` to `y`. Returns `None` if the state is out of range.
+fn state_cell(prefix: Option<char>, letter: char) -> Option<Cell> {
+    let offset = prefix.map_or(0, |p| (p as usize - 'p' as usize + 1) * STATES_PER_PREFIX);
+    let state = offset + (letter as usize - 'A' as usize) + 1;
+    if state > u8::MAX as usize { None } else { Some(Cell::from_state(state as u8)) }
+}
+
+/// Returns the multi-state tag for `cell`: `.` for dead, `A` for live, and letters from
+/// `B` onwards for each dying state
+fn state_tag(cell: &Cell) -> String {
+    match cell.state() as usize {
+        0 => ".".to_string(),
+        n => {
+            let (prefix, letter) = ((n - 1) / STATES_PER_PREFIX, (n - 1) % STATES_PER_PREFIX);
+            let letter = (b'A' + letter as u8) as char;
+            match prefix {
+                0 => letter.to_string(),
+                p => format!("{}{}", (b'p' + p as u8 - 1) as char, letter),
+            }
+        },
+    }
+}
+
+/// Parses the [RLE](http://conwaylife.com/wiki/Run_Length_Encoded) format from a buffered stream
+///
+/// Cells of rules with more than two states, such as Generations rules, may be given
+/// using multi-state letters, where `.` is dead, `A` is live and each later letter is the
+/// next dying state. States beyond `X` are written with a prefix, so `pA` follows `X`.
+pub fn parse_rle<R>(reader: R) -> ParseResult
+    where R: io::BufRead
+{
+    let mut name = String::new();
+    let mut comment = String::new();
+    let mut author = String::new();
+    let mut rule = None;
+    let mut offset = None;
+    let mut header: Option<Header> = None;
+
+    let mut cells = Vec::new();
+    let (mut x, mut y): (usize, usize) = (0, 0);
+    let mut count: Option<usize> = None;
+    let mut prefix: Option<char> = None;
+
+    'lines: for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        let header = match header {
+            Some(ref h) => h,
+            None => {
+                if line.is_empty() {
+                    continue;
+                }
+                if line.starts_with('#') {
+                    let value = line.get(2..).unwrap_or("").trim();
+                    match line.get(1..2) {
+                        Some("N") => name.push_str(value),
+                        Some("C") | Some("c") => {
+                            if !comment.is_empty() {
+                                comment.push('\n');
+                            }
+                            comment.push_str(value);
+                        },
+                        Some("O") => author.push_str(value),
+                        Some("P") | Some("R") => offset = parse_offset(value),
+                        Some("r") => rule = Some(value.to_string()),
+                        _ => {}, //Ignore unknown comment lines
+                    }
+                    continue;
+                }
+                if !line.starts_with('x') {
+                    return Err(ParseError::HeaderMissing);
+                }
+                let h = parse_header(line).ok_or(ParseError::InvalidHeader)?;
+                //Reject sizes which could not be allocated rather than aborting
+                let size = h.width.checked_mul(h.height).ok_or(ParseError::InvalidHeader)?;
+                cells.try_reserve_exact(size).map_err(|_| ParseError::InvalidHeader)?;
+                cells.resize(size, Cell::Dead);
+                if h.rule.is_some() {
+                    rule = h.rule.clone();
+                }
+                header = Some(h);
+                continue;
+            },
+        };
+
+        for c in line.chars() {
+            if let Some(d) = c.to_digit(10) {
+                let value = count.unwrap_or(0).checked_mul(10).and_then(|c| c.checked_add(d as usize));
+                count = Some(value.ok_or(ParseError::Invalid)?);
+                continue;
+            }
+            if let 'p' ..= 'y' = c {
+                //Prefix of a multi-state letter
+                if prefix.replace(c).is_some() {
+                    return Err(ParseError::Invalid);
+                }
+                continue;
+            }
+            let n = count.take().unwrap_or(1);
+            let cell = match (prefix.take(), c) {
+                (None, 'b') | (None, '.') => Cell::Dead,
+                (None, 'o')               => Cell::Live,
+                (p, 'A' ..= 'X')          => state_cell(p, c).ok_or(ParseError::Invalid)?,
+                (None, '$') => {
+                    x = 0;
+                    y = y.checked_add(n).ok_or(ParseError::OutOfBounds)?;
+                    continue;
+                },
+                (None, '!') => break 'lines,
+                (None, c) if c.is_whitespace() => continue,
+                _ => return Err(ParseError::Invalid),
+            };
+            let end = x.checked_add(n).ok_or(ParseError::OutOfBounds)?;
+            if !cell.is_dead() {
+                if y >= header.height || end > header.width {
+                    return Err(ParseError::OutOfBounds);
+                }
+                for target in &mut cells[y * header.width + x .. y * header.width + end] {
+                    *target = cell.clone();
+                }
+            }
+            x = end;
+        }
+    }
+
+    let (width, height) = match header {
+        Some(h) => (h.width, h.height),
+        None    => return Err(ParseError::HeaderMissing),
+    };
+
+    Ok(Rle {
+        name,
+        comment,
+        author,
+        rule,
+        offset,
+        data: Grid::from_raw(width, height, cells)
+    })
+}
+
+/// The maximum length of a line written by `write_rle`
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Accumulates body tags, wrapping lines before they exceed `MAX_LINE_LENGTH`
+struct BodyWriter<W> {
+    writer: W,
+    line_length: usize,
+}
+
+impl<W> BodyWriter<W> where W: io::Write {
+    fn write_run(&mut self, count: usize, tag: &str) -> io::Result<()> {
+        let run = if count > 1 { format!("{}{}", count, tag) } else { tag.to_string() };
+        if self.line_length + run.len() > MAX_LINE_LENGTH {
+            self.writer.write_all(b"\n")?;
+            self.line_length = 0;
+        }
+        self.writer.write_all(run.as_bytes())?;
+        self.line_length += run.len();
+        Ok(())
+    }
+}
+
+/// Writes the [RLE](http://conwaylife.com/wiki/Run_Length_Encoded) format to a stream
+///
+/// When `crop` is set only the smallest region containing every live or dying cell is
+/// written, and any `offset` is moved to the top left cell of that region. Consecutive
+/// row ends are merged into a single `n$` run, trailing dead cells in each row are
+/// omitted and body lines are wrapped at 70 columns. Grids containing dying cells are
+/// written with multi-state letters, as described by `parse_rle`.
+pub fn write_rle<W>(writer: W, rle: &Rle, crop: bool) -> io::Result<()>
+    where W: io::Write
+{
+    let grid = &rle.data;
+    let (x0, y0, width, height) = if crop {
+        grid.occupied_bounds().unwrap_or((0, 0, 0, 0))
+    } else {
+        (0, 0, grid.width(), grid.height())
+    };
+
+    let mut writer = BodyWriter { writer, line_length: 0 };
+    {
+        let w = &mut writer.writer;
+        if !rle.name.is_empty() {
+            writeln!(w, "#N {}", rle.name)?;
+        }
+        if !rle.author.is_empty() {
+            writeln!(w, "#O {}", rle.author)?;
+        }
+        if !rle.comment.is_empty() {
+            for line in rle.comment.lines() {
+                writeln!(w, "#C {}", line)?;
+            }
+        }
+        if let Some((x, y)) = rle.offset {
+            writeln!(w, "#P {} {}", x + x0 as i64, y + y0 as i64)?;
+        }
+        match rle.rule {
+            Some(ref rule) => writeln!(w, "x = {}, y = {}, rule = {}", width, height, rule)?,
+            None           => writeln!(w, "x = {}, y = {}", width, height)?,
+        }
+    }
+
+    let multi_state = grid.iter_cells().any(|(_, _, cell)| cell.is_dying());
+    let tag = |cell: &Cell| -> String {
+        match (multi_state, cell.is_live()) {
+            (true, _)      => state_tag(cell),
+            (false, true)  => "o".to_string(),
+            (false, false) => "b".to_string(),
+        }
+    };
+
+    let mut row_ends = 0;
+    for row in grid.iter_rows().skip(y0).take(height) {
+        let row = &row[x0 .. x0 + width];
+        //Trailing dead cells are implied by the next row end
+        let len = row.iter().rposition(|c| !c.is_dead()).map_or(0, |i| i + 1);
+        if len > 0 {
+            if row_ends > 0 {
+                writer.write_run(row_ends, "$")?;
+                row_ends = 0;
+            }
+            let mut i = 0;
+            while i < len {
+                let cell = &row[i];
+                let run = row[i .. len].iter().take_while(|c| *c == cell).count();
+                writer.write_run(run, &tag(cell))?;
+                i += run;
+            }
+        }
+        row_ends += 1;
+    }
+    writer.write_run(1, "!")?;
+    writer.writer.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io;
+    use grid::Grid;
+    use grid::Cell::{ Live, Dead };
+    use super::{ Rle, ParseResult, ParseError };
+
+    fn parse(s: &str) -> ParseResult {
+        let bytes = s.to_string().into_bytes();
+        let cursor = io::Cursor::new(bytes);
+        let read = io::BufReader::new(cursor);
+
+        super::parse_rle(read)
+    }
+
+    #[test]
+    fn can_parse_simple_rle() {
+
+        const RLE: &str = "#N Glider
+#O Richard K. Guy
+#C The smallest, most common, and first discovered spaceship.
+#C Diagonal, has period 4 and speed c/4.
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!
+";
+
+        let result = parse(RLE);
+
+        assert!(result.is_ok(), "Result is not Ok");
+
+        let value = result.unwrap();
+
+        assert_eq!(value.name, "Glider");
+        assert_eq!(value.author, "Richard K. Guy");
+        assert_eq!(value.comment, "The smallest, most common, and first discovered spaceship.\n\
+                                   Diagonal, has period 4 and speed c/4.");
+        assert_eq!(value.rule, Some("B3/S23".to_string()));
+        assert_eq!(value.offset, None);
+
+        let expected = Grid::from_raw(3, 3, vec![
+            Dead, Live, Dead,
+            Dead, Dead, Live,
+            Live, Live, Live,
+        ]);
+
+        assert_eq!(value.data, expected);
+    }
+
+    #[test]
+    fn can_parse_body_split_across_lines() {
+
+        const RLE: &str = "x=5,y=4
+2o$
+o2bo2$3b
+2o!";
+
+        let value = parse(RLE).unwrap();
+
+        let expected = Grid::from_raw(5, 4, vec![
+            Live, Live, Dead, Dead, Dead,
+            Live, Dead, Dead, Live, Dead,
+            Dead, Dead, Dead, Dead, Dead,
+            Dead, Dead, Dead, Live, Live,
+        ]);
+
+        assert_eq!(value.data, expected);
+        assert_eq!(value.rule, None);
+    }
+
+    #[test]
+    fn can_parse_offset_and_legacy_rule() {
+
+        const RLE: &str = "#P -3 12\n#r 23/3\nx = 1, y = 1\no!";
+
+        let value = parse(RLE).unwrap();
+
+        assert_eq!(value.offset, Some((-3, 12)));
+        assert_eq!(value.rule, Some("23/3".to_string()));
+        assert_eq!(value.name, "");
+        assert_eq!(value.comment, "");
+    }
+
+    #[test]
+    fn can_parse_rule_containing_commas() {
+        use rules::LargerThanLife;
+
+        const RLE: &str = "x = 2, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\n2o!";
+
+        let value = parse(RLE).unwrap();
+
+        assert_eq!(value.rule, Some("R5,C0,M1,S34..58,B34..45,NM".to_string()));
+        assert_eq!(value.data, Grid::from_raw(2, 1, vec![Live, Live]));
+        assert!(value.rule.unwrap().parse::<LargerThanLife>().is_ok());
+    }
+
+    #[test]
+    fn ignores_data_after_terminator() {
+
+        const RLE: &str = "x = 2, y = 1\n2o!\nThis is not part of the pattern";
+
+        let value = parse(RLE).unwrap();
+
+        assert_eq!(value.data, Grid::from_raw(2, 1, vec![Live, Live]));
+    }
+
+    #[test]
+    fn parse_fails_when_header_missing() {
+
+        match parse("#N Glider\nbob$2bo$3o!") {
+            Err(ParseError::HeaderMissing) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        match parse("#N Glider\n") {
+            Err(ParseError::HeaderMissing) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_fails_when_header_invalid() {
+
+        match parse("x = 3\nbob$2bo$3o!") {
+            Err(ParseError::InvalidHeader) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        match parse("x = three, y = 3\nbob$2bo$3o!") {
+            Err(ParseError::InvalidHeader) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_fails_when_header_too_large() {
+
+        match parse("x = 18446744073709551615, y = 2\no!") {
+            Err(ParseError::InvalidHeader) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        match parse("x = 4294967296, y = 4294967295\no!") {
+            Err(ParseError::InvalidHeader) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_fails_when_run_count_overflows() {
+
+        match parse("x = 2, y = 2\n99999999999999999999999o!") {
+            Err(ParseError::Invalid) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        match parse("x = 2, y = 2\n18446744073709551615b2o!") {
+            Err(ParseError::OutOfBounds) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_fails_when_cells_out_of_bounds() {
+
+        match parse("x = 2, y = 2\n3o!") {
+            Err(ParseError::OutOfBounds) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        match parse("x = 2, y = 2\n2$o!") {
+            Err(ParseError::OutOfBounds) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_fails_when_invalid_chars_in_body() {
+
+        match parse("x = 3, y = 1\nozo!") {
+            Err(ParseError::Invalid) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    fn write(rle: &Rle, crop: bool) -> String {
+        let mut bytes = Vec::new();
+        super::write_rle(&mut bytes, rle, crop).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn can_write_simple_rle() {
+        use grid::Cell::Dead as X;
+        use grid::Cell::Live as O;
+
+        let mut rle = Rle::new(Grid::from_raw(3, 3, vec![
+            X, O, X,
+            X, X, O,
+            O, O, O,
+        ]));
+        rle.name = "Glider".to_string();
+        rle.author = "Richard K. Guy".to_string();
+        rle.comment = "First line\nSecond line".to_string();
+        rle.rule = Some("B3/S23".to_string());
+
+        let expected = "#N Glider
+#O Richard K. Guy
+#C First line
+#C Second line
+x = 3, y = 3, rule = B3/S23
+bo$2bo$3o!
+";
+
+        assert_eq!(write(&rle, false), expected);
+    }
+
+    #[test]
+    fn can_merge_empty_rows() {
+        use grid::Cell::Dead as X;
+        use grid::Cell::Live as O;
+
+        let rle = Rle::new(Grid::from_raw(3, 5, vec![
+            O, X, X,
+            X, X, X,
+            X, X, X,
+            X, X, O,
+            X, X, X,
+        ]));
+
+        assert_eq!(write(&rle, false), "x = 3, y = 5\no3$2bo!\n");
+    }
+
+    #[test]
+    fn can_write_cropped_rle() {
+        let mut grid = Grid::create_dead(10, 10);
+        grid.set_cell(4, 3, Live);
+        grid.set_cell(6, 5, Live);
+
+        let rle = Rle::new(grid);
+
+        assert_eq!(write(&rle, true), "x = 3, y = 3\no2$2bo!\n");
+        assert_eq!(write(&Rle::new(Grid::create_dead(4, 4)), true), "x = 0, y = 0\n!\n");
+    }
+
+    #[test]
+    fn can_round_trip_cropped_rle_with_offset() {
+        let mut grid = Grid::create_dead(10, 10);
+        grid.set_cell(4, 3, Live);
+        grid.set_cell(6, 5, Live);
+
+        let mut rle = Rle::new(grid.clone());
+        rle.offset = Some((-2, 7));
+
+        let text = write(&rle, true);
+        assert_eq!(text, "#P 2 10\nx = 3, y = 3\no2$2bo!\n");
+
+        let value = parse(&text).unwrap();
+        assert_eq!(value.offset, Some((2, 10)));
+        assert_eq!(value.data, grid.crop(4, 3, 3, 3));
+    }
+
+    #[test]
+    fn wraps_lines_at_70_columns() {
+        let grid = Grid::from_fn(200, 1, |x, _| if x % 2 == 0 { Live } else { Dead });
+
+        let text = write(&Rle::new(grid.clone()), false);
+
+        for line in text.lines() {
+            assert!(line.len() <= 70, "Line too long: {}", line);
+        }
+        assert_eq!(parse(&text).unwrap().data, grid);
+    }
+
+    #[test]
+    fn can_round_trip_rle() {
+        const RLE: &str = "#N Gosper glider gun
+#C A true period 30 glider gun.
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+        let value = parse(RLE).unwrap();
+
+        assert_eq!(write(&value, false), RLE);
+    }
+
+    #[test]
+    fn can_round_trip_multi_state_rle() {
+        use grid::Cell::Dying;
+
+        const RLE: &str = "x = 4, y = 3, rule = B2/S/C3
+.2A$AB.pA$3B!
+";
+
+        let value = parse(RLE).unwrap();
+
+        assert_eq!(value.data.cell_at(1, 0), &Live);
+        assert_eq!(value.data.cell_at(0, 1), &Live);
+        assert_eq!(value.data.cell_at(1, 1), &Dying(2));
+        assert_eq!(value.data.cell_at(3, 1), &Dying(25));
+        assert_eq!(value.data.cell_at(3, 2), &Dead);
+        assert_eq!(write(&value, false), RLE);
+    }
+
+    #[test]
+    fn parse_fails_with_invalid_multi_state_letters() {
+        for body in &["pqA!", "p$!", "yX!"] {
+            match parse(&format!("x = 3, y = 1\n{}", body)) {
+                Err(ParseError::Invalid) => {},
+                other => panic!("Unexpected result: {:?}", other),
+            }
+        }
+    }
+}
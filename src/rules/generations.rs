@@ -0,0 +1,143 @@
+//! Module for parsing [Generations](http://conwaylife.com/wiki/Generations) rulestrings
+//! such as `/2/3` (Brian's Brain) or `345/2/4` (Star Wars)
+
+use grid::{ Cell, MAX_STATES };
+use super::{ Rule, LifeLike, ParseError };
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A Generations ruleset, which extends a life-like ruleset with refractory states.
+///
+/// Live cells which do not survive become `Dying` rather than dead, and then pass through
+/// each remaining state in turn, one per generation, before dying. Dying cells are not
+/// counted as neighbours and cannot be born or survive.
+///
+/// Rules may be parsed from either `B2/S/C3` or `/2/3` (survival/birth/states) notation,
+/// and are displayed in `B2/S/C3` notation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Generations {
+    rule: LifeLike,
+    states: usize,
+}
+
+impl Generations {
+    /// Constructs a new `Generations` ruleset from the life-like births and survivals and
+    /// the total number of states, including live and dead
+    ///
+    /// Panics if `states` is not between 2 and 256.
+    pub fn new(rule: LifeLike, states: usize) -> Generations {
+        assert!((2..=MAX_STATES).contains(&states), "State count {} out of range", states);
+        Generations { rule, states }
+    }
+
+    /// Constructs the `B2/S/C3` rules of Brian's Brain
+    pub fn brians_brain() -> Generations {
+        Generations::new(LifeLike::new(&[2], &[]), 3)
+    }
+
+    /// Returns the life-like births and survivals of this ruleset
+    pub fn life_rule(&self) -> LifeLike {
+        self.rule
+    }
+}
+
+impl Rule for Generations {
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        let next = match *cell {
+            Cell::Dead if self.rule.is_birth(neighbours) => Cell::Live,
+            Cell::Dead => Cell::Dead,
+            Cell::Live if self.rule.is_survival(neighbours) => Cell::Live,
+            _ => {
+                let next = cell.state() as usize + 1;
+                if next < self.states { Cell::from_state(next as u8) } else { Cell::Dead }
+            },
+        };
+        Some(next)
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        if self.states == 2 { Some(self.rule) } else { None }
+    }
+
+    fn states(&self) -> usize {
+        self.states
+    }
+}
+
+impl fmt::Display for Generations {
+    /// Formats the rule in `B2/S/C3` notation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/C{}", self.rule, self.states)
+    }
+}
+
+impl FromStr for Generations {
+    type Err = ParseError;
+
+    /// Parses a rule in `B2/S/C3`, `B2/S/3` or `/2/3` notation
+    fn from_str(s: &str) -> Result<Generations, ParseError> {
+        let s = s.trim();
+        let (rule, states) = match s.rfind('/') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None    => return Err(ParseError::InvalidFormat),
+        };
+        let states = states.trim_start_matches(&['C', 'c'][..]);
+        let states = match states.parse() {
+            Ok(n) if (2..=MAX_STATES).contains(&n) => n,
+            _                                       => return Err(ParseError::InvalidStates),
+        };
+        Ok(Generations { rule: rule.parse()?, states })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Generations;
+    use rules::{ Rule, LifeLike, ParseError };
+    use grid::Cell::{ Live, Dead, Dying };
+
+    #[test]
+    fn can_parse_generations_rules() {
+        assert_eq!(Ok(Generations::brians_brain()), "/2/3".parse());
+        assert_eq!(Ok(Generations::brians_brain()), "B2/S/C3".parse());
+        assert_eq!(Ok(Generations::brians_brain()), "B2/S/3".parse());
+
+        let star_wars = Generations::new(LifeLike::new(&[2], &[3, 4, 5]), 4);
+        assert_eq!(Ok(star_wars), "345/2/4".parse());
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+    }
+
+    #[test]
+    fn fails_with_invalid_rulestrings() {
+        assert_eq!("B3/S23".parse::<Generations>(), Err(ParseError::InvalidStates));
+        assert_eq!("/2/1".parse::<Generations>(), Err(ParseError::InvalidStates));
+        assert_eq!("/2/257".parse::<Generations>(), Err(ParseError::InvalidStates));
+        assert_eq!("3".parse::<Generations>(), Err(ParseError::InvalidFormat));
+        assert_eq!("/9/3".parse::<Generations>(), Err(ParseError::InvalidDigit('9')));
+    }
+
+    #[test]
+    fn can_apply_rule() {
+        let rule: Generations = "345/2/4".parse().unwrap();
+
+        assert_eq!(rule.next_cell(&Dead, 2), Some(Live));
+        assert_eq!(rule.next_cell(&Dead, 3), Some(Dead));
+        assert_eq!(rule.next_cell(&Live, 4), Some(Live));
+        assert_eq!(rule.next_cell(&Live, 2), Some(Dying(2)));
+        assert_eq!(rule.next_cell(&Dying(2), 2), Some(Dying(3)));
+        assert_eq!(rule.next_cell(&Dying(3), 2), Some(Dead));
+        assert_eq!(rule.states(), 4);
+        assert_eq!(rule.life_like(), None);
+    }
+
+    #[test]
+    fn two_state_rules_are_life_like() {
+        let rule: Generations = "23/3/2".parse().unwrap();
+
+        assert_eq!(rule.next_cell(&Live, 1), Some(Dead));
+        assert_eq!(rule.life_like(), Some(LifeLike::conway()));
+    }
+}
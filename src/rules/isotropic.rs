@@ -0,0 +1,385 @@
+//! Module for parsing [isotropic non-totalistic](http://conwaylife.com/wiki/Isotropic_non-totalistic_Life-like_cellular_automaton)
+//! rulestrings in Hensel notation, such as `B2-a/S12` or `B3/S23-a`
+
+use grid::Cell;
+use super::{ Rule, LifeLike, ParseError, CENTRE };
+use super::map;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The letters distinguishing each arrangement of 0 to 4 live neighbours, in the order
+/// they are written. Counts above 4 use the letters of their complement.
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// A configuration of each arrangement described by `LETTERS`, with rows from top to
+/// bottom written left to right
+const REPRESENTATIVES: [&[usize]; 5] = [
+    &[0b000_000_000],
+    &[0b100_000_000, 0b010_000_000],
+    &[0b101_000_000, 0b010_100_000, 0b110_000_000, 0b000_101_000, 0b100_001_000, 0b001_000_100],
+    &[0b101_000_100, 0b010_101_000, 0b110_100_000, 0b111_000_000, 0b010_001_100,
+      0b101_100_000, 0b011_100_000, 0b011_000_100, 0b100_101_000, 0b100_001_100],
+    &[0b101_000_101, 0b010_101_010, 0b111_100_000, 0b101_101_000, 0b110_001_100,
+      0b111_000_100, 0b010_101_100, 0b011_001_100, 0b110_101_000, 0b101_001_100,
+      0b100_101_100, 0b011_100_100, 0b001_101_100],
+];
+
+/// The bits of a configuration holding the eight neighbours
+const NEIGHBOURS: usize = 0b111_101_111;
+
+/// Returns the letters distinguishing each arrangement of `count` live neighbours
+fn letters(count: usize) -> &'static str {
+    LETTERS[count.min(8 - count)]
+}
+
+/// Returns the number of distinct arrangements of `count` live neighbours
+fn arrangements(count: usize) -> usize {
+    letters(count).len().max(1)
+}
+
+/// Returns a configuration of each arrangement of `count` live neighbours
+fn representatives(count: usize) -> Vec<usize> {
+    if count <= 4 {
+        REPRESENTATIVES[count].to_vec()
+    }
+    else {
+        REPRESENTATIVES[8 - count].iter().map(|&config| config ^ NEIGHBOURS).collect()
+    }
+}
+
+/// Returns the bit of a configuration holding the cell at `(x, y)` of the 3x3 square
+fn bit(x: usize, y: usize) -> usize {
+    1 << (8 - (y * 3 + x))
+}
+
+/// Moves each cell of `config` to the position given by `f`
+fn map_cells<F>(config: usize, f: F) -> usize
+    where F: Fn(usize, usize) -> (usize, usize)
+{
+    (0..9)
+        .map(|i| (i % 3, i / 3))
+        .filter(|&(x, y)| config & bit(x, y) != 0)
+        .fold(0, |out, (x, y)| {
+            let (x, y) = f(x, y);
+            out | bit(x, y)
+        })
+}
+
+/// Returns the count of live neighbours and the index of the letter describing their
+/// arrangement for each of the 512 configurations
+fn classify() -> Vec<(usize, usize)> {
+    let mut classes = vec![(0, 0); 512];
+    for count in 0..9 {
+        for (letter, &rep) in representatives(count).iter().enumerate() {
+            let mut config = rep;
+            for _ in 0..4 {
+                config = map_cells(config, |x, y| (2 - y, x));
+                let reflected = map_cells(config, |x, y| (2 - x, y));
+                for &c in &[config, reflected] {
+                    classes[c] = (count, letter);
+                    classes[c | CENTRE] = (count, letter);
+                }
+            }
+        }
+    }
+    classes
+}
+
+/// An isotropic non-totalistic ruleset, in which births and survivals depend on the
+/// arrangement of live neighbours, up to rotation and reflection, as well as their count.
+///
+/// Rules may be parsed from Hensel notation, such as `B2-a/S12`, in which each count may
+/// be followed by the letters of the arrangements it applies to, or by `-` and the letters
+/// of the arrangements it does not apply to. Rules are displayed in the same notation.
+///
+/// The next state of every configuration is precomputed into a lookup table, which
+/// `World` uses when stepping with a Moore neighbourhood. Since a count of neighbours
+/// alone is not enough to apply the rule, `next_cell` returns `None` unless the rule is
+/// life-like, and `World` rejects other neighbourhoods for rules which are not.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Isotropic {
+    table: [bool; 512],
+    life_like: Option<LifeLike>,
+}
+
+impl Isotropic {
+    /// Constructs the ruleset with the given arrangements for each count, where bit `i` of
+    /// `births[n]` is set if a dead cell with the `i`th arrangement of `n` neighbours is born
+    fn from_masks(births: &[u16; 9], survivals: &[u16; 9]) -> Isotropic {
+        let mut table = [false; 512];
+        for (config, (count, letter)) in classify().into_iter().enumerate() {
+            let masks = if config & CENTRE != 0 { survivals } else { births };
+            table[config] = masks[count] & (1 << letter) != 0;
+        }
+        Isotropic::from_table(table)
+    }
+
+    fn from_table(table: [bool; 512]) -> Isotropic {
+        Isotropic { table, life_like: map::table_life_like(&table) }
+    }
+
+    /// Constructs the isotropic ruleset equivalent to a life-like ruleset
+    pub fn from_life_like(rule: LifeLike) -> Isotropic {
+        Isotropic::from_table(map::life_like_table(rule))
+    }
+
+    /// Returns true if a cell in `configuration` is live in the next generation.
+    ///
+    /// See `rules::configuration` for the layout of configurations.
+    #[inline]
+    pub fn is_live(&self, configuration: usize) -> bool {
+        self.table[configuration]
+    }
+
+    /// Writes the counts and letters of the arrangements which give a live cell, for
+    /// either births or survivals
+    fn write_section(&self, f: &mut fmt::Formatter, centre: usize) -> fmt::Result {
+        for count in 0..9 {
+            let reps = representatives(count);
+            let letters: Vec<char> = letters(count).chars().collect();
+            let (present, missing): (Vec<usize>, Vec<usize>) = (0..reps.len())
+                .partition(|&i| self.table[reps[i] | centre]);
+            if present.is_empty() {
+                continue;
+            }
+            write!(f, "{}", count)?;
+            if missing.is_empty() {
+                continue;
+            }
+            let (prefix, shown) = if present.len() <= missing.len() { ("", present) } else { ("-", missing) };
+            write!(f, "{}", prefix)?;
+            for i in shown {
+                write!(f, "{}", letters[i])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Rule for Isotropic {
+    /// Applies the rule to a count of neighbours, which is only possible if the rule is
+    /// life-like
+    ///
+    /// Returns `None` if the rule is not life-like.
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        self.life_like.and_then(|rule| rule.next_cell(cell, neighbours))
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        self.life_like
+    }
+
+    fn transitions(&self) -> Option<[bool; 512]> {
+        Some(self.table)
+    }
+}
+
+impl fmt::Display for Isotropic {
+    /// Formats the rule in `B2-a/S12` notation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        self.write_section(f, 0)?;
+        write!(f, "/S")?;
+        self.write_section(f, CENTRE)
+    }
+}
+
+/// Parses a run of counts, each followed by optional arrangement letters, into a mask of
+/// arrangements for each count
+fn parse_counts(s: &str) -> Result<[u16; 9], ParseError> {
+    let mut masks = [0; 9];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = match c.to_digit(10) {
+            Some(n) if n <= 8 => n as usize,
+            _                 => return Err(ParseError::InvalidDigit(c)),
+        };
+        let negate = chars.next_if_eq(&'-').is_some();
+        let mut mask = 0;
+        while let Some(letter) = chars.next_if(|c| !c.is_ascii_digit()) {
+            match letters(count).find(letter) {
+                Some(i) => mask |= 1 << i,
+                None    => return Err(ParseError::InvalidLetter(letter)),
+            }
+        }
+        let all = (1 << arrangements(count)) - 1;
+        masks[count] |= match (negate, mask) {
+            (false, 0) => all,
+            (true, 0)  => return Err(ParseError::InvalidFormat),
+            (false, m) => m,
+            (true, m)  => all & !m,
+        };
+    }
+    Ok(masks)
+}
+
+impl FromStr for Isotropic {
+    type Err = ParseError;
+
+    /// Parses a rule in `B2-a/S12`, `B2-aS12` or `12/2-a` notation
+    fn from_str(s: &str) -> Result<Isotropic, ParseError> {
+        let s = s.trim();
+
+        if !s.starts_with(&['B', 'b', 'S', 's'][..]) {
+            //Survival/birth notation
+            let mut parts = s.split('/');
+            return match (parts.next(), parts.next(), parts.next()) {
+                (Some(s), Some(b), None) => Ok(Isotropic::from_masks(&parse_counts(b)?, &parse_counts(s)?)),
+                _                        => Err(ParseError::InvalidFormat),
+            };
+        }
+
+        //Birth/survival notation, in either order and with an optional separator
+        let (mut births, mut survivals) = (None, None);
+        let mut rest = s;
+        while let Some(prefix) = rest.chars().next() {
+            let slot = match prefix {
+                'B' | 'b' => &mut births,
+                'S' | 's' => &mut survivals,
+                 _        => return Err(ParseError::InvalidFormat),
+            };
+            if slot.is_some() {
+                return Err(ParseError::InvalidFormat);
+            }
+            let tail = &rest[prefix.len_utf8()..];
+            let end = tail.find(&['/', 'B', 'b', 'S', 's'][..]).unwrap_or(tail.len());
+            *slot = Some(parse_counts(&tail[..end])?);
+            rest = tail[end..].trim_start_matches('/');
+        }
+
+        match (births, survivals) {
+            (Some(births), Some(survivals)) => Ok(Isotropic::from_masks(&births, &survivals)),
+            _                               => Err(ParseError::InvalidFormat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ Isotropic, classify, arrangements };
+    use grid::Cell;
+    use rules::{ self, Rule, LifeLike, ParseError, RuleError, OffsetNeighbours, Edges };
+    use world::World;
+    use bitgrid::tests::make_random_grid;
+
+    #[test]
+    fn arrangements_cover_every_configuration() {
+        let classes = classify();
+        for count in 0..9 {
+            let mut sizes = vec![0; arrangements(count)];
+            for (config, &(n, letter)) in classes.iter().enumerate() {
+                if n == count && config & super::CENTRE == 0 {
+                    assert_eq!((config & super::NEIGHBOURS).count_ones() as usize, count);
+                    sizes[letter] += 1;
+                }
+            }
+            assert!(sizes.iter().all(|&size| size > 0), "{} neighbours: {:?}", count, sizes);
+        }
+        // Every configuration is classified by its count
+        assert!(classes.iter().enumerate().all(|(config, &(n, _))| {
+            (config & super::NEIGHBOURS).count_ones() as usize == n
+        }));
+    }
+
+    #[test]
+    fn can_parse_hensel_notation() {
+        let rule: Isotropic = "B2a/S".parse().unwrap();
+
+        assert!(rule.is_live(0b110_000_000));
+        assert!(rule.is_live(0b000_001_001));
+        assert!(!rule.is_live(0b010_000_010));
+        assert!(!rule.is_live(0b110_010_000));
+        assert_eq!(rule.life_like(), None);
+
+        assert_eq!("B2-a/S12".parse::<Isotropic>().unwrap().to_string(), "B2-a/S12");
+        assert_eq!("B3/S23-a".parse::<Isotropic>().unwrap().to_string(), "B3/S23-a");
+        assert_eq!("B2ce3ai/S2-i34q".parse::<Isotropic>(), "B2ec3ia/S2cekna34q".parse());
+        assert_eq!("B3/S2-i34q".parse::<Isotropic>(), "2-i34q/3".parse());
+    }
+
+    #[test]
+    fn can_round_trip_display() {
+        for s in &["B3/S23", "B2-a/S12", "B2ei3/S2-i34q", "B2cen3-ckq4t/S1c2-cn3i4-a5cei", "B/S012345678"] {
+            let rule: Isotropic = s.parse().unwrap();
+            assert_eq!(&rule.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn fails_with_invalid_rulestrings() {
+        let cases = &[
+            ("B3",      ParseError::InvalidFormat),
+            ("B2-/S23", ParseError::InvalidFormat),
+            ("B2x/S23", ParseError::InvalidLetter('x')),
+            ("B1a/S23", ParseError::InvalidLetter('a')),
+            ("B9/S23",  ParseError::InvalidDigit('9')),
+        ];
+        for &(s, ref expected) in cases {
+            assert_eq!(s.parse::<Isotropic>().as_ref(), Err(expected), "Parsing {:?}", s);
+        }
+    }
+
+    #[test]
+    fn totalistic_rules_are_life_like() {
+        let rule: Isotropic = "B3/S23".parse().unwrap();
+
+        assert_eq!(rule.life_like(), Some(LifeLike::conway()));
+        assert_eq!(rule, Isotropic::from_life_like(LifeLike::conway()));
+    }
+
+    #[test]
+    fn can_step_world_with_isotropic_rules() {
+        let mut expected = World::new(make_random_grid(20, 20, 9));
+        let mut actual = World::new(make_random_grid(20, 20, 9));
+        actual.set_rules(Isotropic::from_life_like(LifeLike::conway())).unwrap();
+        for _ in 0..10 {
+            expected.step_mut();
+            actual.step_mut();
+            assert_eq!(actual.grid(), expected.grid());
+        }
+    }
+
+    #[test]
+    fn isotropic_rules_commute_with_rotation() {
+        let rule: Isotropic = "B2-a3i/S1e2k3-r".parse().unwrap();
+        let grid = make_random_grid(16, 16, 4);
+
+        let mut w = World::new(grid.clone());
+        let mut rotated = World::new(grid.rotate_90());
+        w.set_rules(rule).unwrap();
+        rotated.set_rules(rule).unwrap();
+        for _ in 0..5 {
+            w.step_mut();
+            rotated.step_mut();
+            assert_eq!(rotated.grid(), &w.grid().rotate_90());
+        }
+    }
+
+    #[test]
+    fn rejects_neighbour_functions() {
+        let mut w = World::new(make_random_grid(8, 8, 1));
+        w.set_rules("B2-a/S12".parse::<Isotropic>().unwrap()).unwrap();
+
+        assert_eq!(w.set_neighbours(rules::torus_neighbours), Err(RuleError::NeedsMooreNeighbourhood));
+    }
+
+    #[test]
+    fn rejects_other_neighbourhoods() {
+        let mut w = World::new(make_random_grid(8, 8, 1));
+        w.set_neighbours(OffsetNeighbours::von_neumann(Edges::Torus)).unwrap();
+
+        let rule: Isotropic = "B2-a/S12".parse().unwrap();
+        assert_eq!(w.set_rules(rule), Err(RuleError::NeedsMooreNeighbourhood));
+        assert_eq!(rule.next_cell(&Cell::Dead, 2), None);
+
+        // The world keeps stepping with its previous rules
+        let mut expected = World::new(w.grid().clone());
+        expected.set_neighbours(OffsetNeighbours::von_neumann(Edges::Torus)).unwrap();
+        w.step_mut();
+        expected.step_mut();
+        assert_eq!(w.grid(), expected.grid());
+    }
+}
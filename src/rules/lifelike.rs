@@ -0,0 +1,204 @@
+//! Module for parsing [life-like](http://conwaylife.com/wiki/Life-like_cellular_automaton)
+//! rulestrings such as `B3/S23`
+
+use grid::Cell;
+use super::{ Rule, ParseError };
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A life-like ruleset, described by the neighbour counts which cause a dead cell
+/// to be born and a live cell to survive.
+///
+/// Rules may be parsed from either `B3/S23` or `23/3` (survival/birth) notation,
+/// and are displayed in `B3/S23` notation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LifeLike {
+    births: u16,
+    survivals: u16,
+}
+
+impl LifeLike {
+    /// Constructs a new `LifeLike` ruleset from the neighbour counts which cause birth
+    /// and survival
+    ///
+    /// Panics if any count is greater than 8.
+    pub fn new(births: &[usize], survivals: &[usize]) -> LifeLike {
+        fn mask(counts: &[usize]) -> u16 {
+            counts.iter().fold(0, |m, &n| {
+                assert!(n <= 8, "Neighbour count {} out of range", n);
+                m | (1 << n)
+            })
+        }
+        LifeLike { births: mask(births), survivals: mask(survivals) }
+    }
+
+    /// Constructs the `B3/S23` rules of Conway's Game of Life
+    pub fn conway() -> LifeLike {
+        LifeLike::new(&[3], &[2, 3])
+    }
+
+    /// Returns true if a dead cell with `neighbours` live neighbours is born
+    #[inline]
+    pub fn is_birth(&self, neighbours: usize) -> bool {
+        neighbours <= 8 && self.births & (1 << neighbours) != 0
+    }
+
+    /// Returns true if a live cell with `neighbours` live neighbours survives
+    #[inline]
+    pub fn is_survival(&self, neighbours: usize) -> bool {
+        neighbours <= 8 && self.survivals & (1 << neighbours) != 0
+    }
+}
+
+impl Rule for LifeLike {
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        let live = match *cell {
+            Cell::Live => self.is_survival(neighbours),
+            _          => self.is_birth(neighbours),
+        };
+        Some(if live { Cell::Live } else { Cell::Dead })
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        Some(*self)
+    }
+}
+
+impl Default for LifeLike {
+    fn default() -> LifeLike {
+        LifeLike::conway()
+    }
+}
+
+impl fmt::Display for LifeLike {
+    /// Formats the rule in `B3/S23` notation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for n in (0..9).filter(|&n| self.is_birth(n)) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..9).filter(|&n| self.is_survival(n)) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+pub type ParseResult = Result<LifeLike, ParseError>;
+
+/// Parses a run of neighbour count digits into a bit mask
+fn parse_counts(s: &str) -> Result<u16, ParseError> {
+    s.chars().try_fold(0, |mask, c| {
+        match c.to_digit(10) {
+            Some(n) if n <= 8 => Ok(mask | (1 << n)),
+            _                 => Err(ParseError::InvalidDigit(c)),
+        }
+    })
+}
+
+impl FromStr for LifeLike {
+    type Err = ParseError;
+
+    /// Parses a rule in `B3/S23`, `B3S23` or `23/3` notation
+    fn from_str(s: &str) -> ParseResult {
+        let s = s.trim();
+
+        if !s.starts_with(&['B', 'b', 'S', 's'][..]) {
+            //Survival/birth notation
+            let mut parts = s.split('/');
+            return match (parts.next(), parts.next(), parts.next()) {
+                (Some(s), Some(b), None) => {
+                    Ok(LifeLike { births: parse_counts(b)?, survivals: parse_counts(s)? })
+                },
+                _ => Err(ParseError::InvalidFormat),
+            };
+        }
+
+        //Birth/survival notation, in either order and with an optional separator
+        let (mut births, mut survivals) = (None, None);
+        let mut rest = s;
+        while let Some(prefix) = rest.chars().next() {
+            let slot = match prefix {
+                'B' | 'b' => &mut births,
+                'S' | 's' => &mut survivals,
+                 _        => return Err(ParseError::InvalidFormat),
+            };
+            if slot.is_some() {
+                return Err(ParseError::InvalidFormat);
+            }
+            let tail = &rest[prefix.len_utf8()..];
+            let end = tail.find(|c: char| c == '/' || c.is_alphabetic()).unwrap_or(tail.len());
+            *slot = Some(parse_counts(&tail[..end])?);
+            rest = tail[end..].trim_start_matches('/');
+        }
+
+        match (births, survivals) {
+            (Some(births), Some(survivals)) => Ok(LifeLike { births, survivals }),
+            _                               => Err(ParseError::InvalidFormat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ LifeLike, ParseResult, ParseError };
+    use rules::Rule;
+    use grid::Cell::{ Live, Dead };
+
+    #[test]
+    fn can_parse_birth_survival_notation() {
+        assert_eq!(Ok(LifeLike::conway()), "B3/S23".parse());
+        assert_eq!(Ok(LifeLike::conway()), "b3/s23".parse());
+        assert_eq!(Ok(LifeLike::conway()), "B3S23".parse());
+        assert_eq!(Ok(LifeLike::conway()), "S23/B3".parse());
+        assert_eq!(Ok(LifeLike::new(&[3, 6], &[2, 3])), "B36/S23".parse());
+        assert_eq!(Ok(LifeLike::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8])), "B3678/S34678".parse());
+        assert_eq!(Ok(LifeLike::new(&[2], &[])), "B2/S".parse());
+    }
+
+    #[test]
+    fn can_parse_survival_birth_notation() {
+        assert_eq!(Ok(LifeLike::conway()), "23/3".parse());
+        assert_eq!(Ok(LifeLike::new(&[3, 6], &[2, 3])), "23/36".parse());
+        assert_eq!(Ok(LifeLike::new(&[2], &[])), "/2".parse());
+    }
+
+    #[test]
+    fn can_round_trip_display() {
+        for s in &["B3/S23", "B36/S23", "B2/S", "B3678/S34678", "B/S012345678"] {
+            let rule: LifeLike = s.parse().unwrap();
+            assert_eq!(&rule.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn fails_with_invalid_rulestrings() {
+        let cases: &[(&str, ParseResult)] = &[
+            ("",         Err(ParseError::InvalidFormat)),
+            ("23",       Err(ParseError::InvalidFormat)),
+            ("23/3/1",   Err(ParseError::InvalidFormat)),
+            ("B3",       Err(ParseError::InvalidFormat)),
+            ("B3/B3",    Err(ParseError::InvalidFormat)),
+            ("B3/X23",   Err(ParseError::InvalidFormat)),
+            ("B39/S23",  Err(ParseError::InvalidDigit('9'))),
+            ("23/3a",    Err(ParseError::InvalidDigit('a'))),
+        ];
+        for &(s, ref expected) in cases {
+            assert_eq!(&s.parse::<LifeLike>(), expected, "Parsing {:?}", s);
+        }
+    }
+
+    #[test]
+    fn can_apply_rule() {
+        let rule = LifeLike::conway();
+
+        assert_eq!(rule.next_cell(&Dead, 3), Some(Live));
+        assert_eq!(rule.next_cell(&Dead, 2), Some(Dead));
+        assert_eq!(rule.next_cell(&Live, 2), Some(Live));
+        assert_eq!(rule.next_cell(&Live, 4), Some(Dead));
+    }
+}
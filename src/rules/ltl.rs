@@ -0,0 +1,444 @@
+//! Module for [Larger than Life](http://conwaylife.com/wiki/Larger_than_Life) rules, which
+//! count neighbours over a large range, described by rulestrings such as
+//! `R5,C0,M1,S34..58,B34..45,NM`
+
+use grid::{ Grid, Cell, MAX_STATES };
+use super::{ Rule, Neighbourhood, Edges, LifeLike, ParseError };
+use super::summed::SummedAreaTable;
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// The largest supported range
+const MAX_RADIUS: usize = 500;
+
+/// The shape of a neighbourhood
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Shape {
+    /// Every cell within a square, written `NM`
+    Moore,
+    /// Every cell within a diamond, whose Manhattan distance is at most the range,
+    /// written `NN`
+    VonNeumann,
+    /// Every cell within a circle of the range plus one half, written `NC`
+    Circular,
+}
+
+impl Shape {
+    /// Returns how far the neighbourhood extends either side of the centre column in the
+    /// row `dy` away from the centre
+    fn half_width(&self, radius: usize, dy: usize) -> usize {
+        match *self {
+            Shape::Moore      => radius,
+            Shape::VonNeumann => radius - dy,
+            Shape::Circular   => {
+                let limit = radius * radius + radius - dy * dy;
+                (0..=radius).take_while(|dx| dx * dx <= limit).last().unwrap_or(0)
+            },
+        }
+    }
+}
+
+/// Counts the live cells within `radius` of each cell in a neighbourhood of the given
+/// `Shape`, not including the cell itself.
+///
+/// When stepping a `World` every cell is counted at once using a summed-area table, so
+/// the Moore neighbourhood takes constant time per cell and other shapes take time
+/// proportional to the radius.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RangeNeighbours {
+    radius: usize,
+    shape: Shape,
+    edges: Edges,
+}
+
+impl RangeNeighbours {
+    /// Constructs a neighbourhood of `shape` extending `radius` cells from its centre
+    ///
+    /// Panics if `radius` is not between 1 and 500.
+    pub fn new(radius: usize, shape: Shape, edges: Edges) -> RangeNeighbours {
+        assert!((1..=MAX_RADIUS).contains(&radius), "Radius {} out of range", radius);
+        RangeNeighbours { radius, shape, edges }
+    }
+
+    /// Returns the number of cells in the neighbourhood, not including the centre
+    pub fn size(&self) -> usize {
+        let r = self.radius;
+        (0..=r).map(|dy| {
+            let row = 2 * self.shape.half_width(r, dy) + 1;
+            if dy == 0 { row } else { 2 * row }
+        }).sum::<usize>() - 1
+    }
+}
+
+impl Neighbourhood for RangeNeighbours {
+    fn count(&self, grid: &Grid, x: usize, y: usize) -> usize {
+        let (r, w, h) = (self.radius as isize, grid.width() as isize, grid.height() as isize);
+        let mut count = 0;
+        for dy in -r..=r {
+            let half = self.shape.half_width(self.radius, dy.unsigned_abs()) as isize;
+            for dx in -half..=half {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                let live = match self.edges {
+                    Edges::Torus    => grid.wrapping_cell_at(nx, ny).is_live(),
+                    Edges::Terminal => {
+                        nx >= 0 && ny >= 0 && nx < w && ny < h &&
+                        grid.cell_at(nx as usize, ny as usize).is_live()
+                    },
+                };
+                count += live as usize;
+            }
+        }
+        count
+    }
+
+    fn count_all(&self, grid: &Grid) -> Option<Vec<usize>> {
+        if grid.width() == 0 || grid.height() == 0 {
+            return Some(Vec::new());
+        }
+        let r = self.radius as isize;
+        let table = SummedAreaTable::new(grid, self.radius, self.edges);
+        let counts = grid.iter_cells().map(|(x, y, cell)| {
+            let (x, y) = (x as isize, y as isize);
+            let total = match self.shape {
+                Shape::Moore => table.count(x - r, y - r, x + r, y + r),
+                _ => (-r..=r).map(|dy| {
+                    let half = self.shape.half_width(self.radius, dy.unsigned_abs()) as isize;
+                    table.count(x - half, y + dy, x + half, y + dy)
+                }).sum(),
+            };
+            total - cell.is_live() as usize
+        });
+        Some(counts.collect())
+    }
+
+    fn moore_edges(&self) -> Option<Edges> {
+        if self.radius == 1 && self.shape == Shape::Moore { Some(self.edges) } else { None }
+    }
+
+    fn edges(&self) -> Option<Edges> {
+        Some(self.edges)
+    }
+
+    fn range(&self) -> Option<(usize, Shape)> {
+        Some((self.radius, self.shape))
+    }
+
+    fn radius(&self) -> Option<usize> {
+        Some(self.radius)
+    }
+}
+
+/// A Larger than Life ruleset.
+///
+/// A dead cell is born if its count of live neighbours is within the birth range, and a
+/// live cell survives if its count is within the survival range. With more than two
+/// states, live cells which do not survive pass through `Dying` states as in
+/// `Generations` rules.
+///
+/// Neighbours are counted by the `RangeNeighbours` returned by `neighbourhood`, which
+/// `World::set_rules` uses in place of the current neighbourhood, keeping its edges.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LargerThanLife {
+    radius: usize,
+    states: usize,
+    middle: bool,
+    survivals: (usize, usize),
+    births: (usize, usize),
+    shape: Shape,
+}
+
+impl LargerThanLife {
+    /// Constructs a new `LargerThanLife` ruleset from its range, number of states, whether
+    /// the middle cell is counted as its own neighbour, its survival and birth ranges and
+    /// its neighbourhood shape, in the order they appear in a rulestring
+    ///
+    /// Panics if `radius` is not between 1 and 500 or `states` is not between 2 and 256.
+    pub fn new(radius: usize,
+               states: usize,
+               middle: bool,
+               survivals: RangeInclusive<usize>,
+               births: RangeInclusive<usize>,
+               shape: Shape) -> LargerThanLife {
+        assert!((1..=MAX_RADIUS).contains(&radius), "Radius {} out of range", radius);
+        assert!((2..=MAX_STATES).contains(&states), "State count {} out of range", states);
+        LargerThanLife {
+            radius,
+            states,
+            middle,
+            survivals: survivals.into_inner(),
+            births: births.into_inner(),
+            shape,
+        }
+    }
+
+    /// Constructs the `R5,C0,M1,S34..58,B34..45,NM` rules of Bosco's Rule
+    pub fn bosco() -> LargerThanLife {
+        LargerThanLife::new(5, 2, true, 34..=58, 34..=45, Shape::Moore)
+    }
+
+    /// Returns the neighbourhood counted by this ruleset
+    pub fn neighbourhood(&self, edges: Edges) -> RangeNeighbours {
+        RangeNeighbours::new(self.radius, self.shape, edges)
+    }
+}
+
+impl Rule for LargerThanLife {
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        let within = |(min, max): (usize, usize), n: usize| min <= n && n <= max;
+        let next = match *cell {
+            Cell::Dead if within(self.births, neighbours) => Cell::Live,
+            Cell::Dead => Cell::Dead,
+            Cell::Live if within(self.survivals, neighbours + self.middle as usize) => Cell::Live,
+            _ => {
+                let next = cell.state() as usize + 1;
+                if next < self.states { Cell::from_state(next as u8) } else { Cell::Dead }
+            },
+        };
+        Some(next)
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        if self.radius != 1 || self.shape != Shape::Moore || self.states != 2 {
+            return None;
+        }
+        let births: Vec<_> = (0..9).filter(|&n| self.next_cell(&Cell::Dead, n) == Some(Cell::Live)).collect();
+        let survivals: Vec<_> = (0..9).filter(|&n| self.next_cell(&Cell::Live, n) == Some(Cell::Live)).collect();
+        Some(LifeLike::new(&births, &survivals))
+    }
+
+    fn states(&self) -> usize {
+        self.states
+    }
+
+    fn range(&self) -> Option<(usize, Shape)> {
+        Some((self.radius, self.shape))
+    }
+}
+
+impl fmt::Display for LargerThanLife {
+    /// Formats the rule in `R5,C0,M1,S34..58,B34..45,NM` notation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let states = if self.states == 2 { 0 } else { self.states };
+        let shape = match self.shape {
+            Shape::Moore      => 'M',
+            Shape::VonNeumann => 'N',
+            Shape::Circular   => 'C',
+        };
+        write!(f, "R{},C{},M{},S{}..{},B{}..{},N{}",
+               self.radius, states, self.middle as u8,
+               self.survivals.0, self.survivals.1, self.births.0, self.births.1, shape)
+    }
+}
+
+/// Parses a non-negative number
+fn parse_number(s: &str) -> Result<usize, ParseError> {
+    match s.chars().find(|c| !c.is_ascii_digit()) {
+        Some(c)              => Err(ParseError::InvalidDigit(c)),
+        None if s.is_empty() => Err(ParseError::InvalidFormat),
+        None                 => s.parse().map_err(|_| ParseError::InvalidFormat),
+    }
+}
+
+/// Parses a range of neighbour counts such as `34..58`
+fn parse_range(s: &str) -> Result<(usize, usize), ParseError> {
+    let mut parts = s.splitn(2, "..");
+    let min = parse_number(parts.next().unwrap_or(""))?;
+    let max = match parts.next() {
+        Some(max) => parse_number(max)?,
+        None      => min,
+    };
+    if min > max {
+        return Err(ParseError::InvalidFormat);
+    }
+    Ok((min, max))
+}
+
+impl FromStr for LargerThanLife {
+    type Err = ParseError;
+
+    /// Parses a rule in `R5,C0,M1,S34..58,B34..45,NM` notation. The neighbourhood shape
+    /// may be omitted, in which case it is `NM`, and a state count of 0 or 1 means two
+    /// states.
+    fn from_str(s: &str) -> Result<LargerThanLife, ParseError> {
+        let s = s.trim();
+        let fields = s.split(',').count();
+        let mut parts = s.split(',').map(str::trim);
+        let mut field = |prefix: char| -> Result<&str, ParseError> {
+            parts.next()
+                .and_then(|part| part.strip_prefix(|c: char| c.eq_ignore_ascii_case(&prefix)))
+                .ok_or(ParseError::InvalidFormat)
+        };
+
+        let radius = parse_number(field('R')?)?;
+        let states = parse_number(field('C')?)?;
+        let middle = match field('M')? {
+            "0" => false,
+            "1" => true,
+            _   => return Err(ParseError::InvalidFormat),
+        };
+        let survivals = parse_range(field('S')?)?;
+        let births = parse_range(field('B')?)?;
+        let shape = if fields == 5 {
+            Shape::Moore
+        }
+        else {
+            match field('N')? {
+                "M" | "m" => Shape::Moore,
+                "N" | "n" => Shape::VonNeumann,
+                "C" | "c" => Shape::Circular,
+                _         => return Err(ParseError::InvalidFormat),
+            }
+        };
+        if parts.next().is_some() {
+            return Err(ParseError::InvalidFormat);
+        }
+
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return Err(ParseError::InvalidRadius);
+        }
+        let states = match states {
+            0 | 1                => 2,
+            n if n <= MAX_STATES => n,
+            _                    => return Err(ParseError::InvalidStates),
+        };
+        Ok(LargerThanLife { radius, states, middle, survivals, births, shape })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ LargerThanLife, RangeNeighbours, Shape };
+    use rules::{ self, Rule, Neighbourhood, Edges, LifeLike, ParseError, RuleError };
+    use world::World;
+    use bitgrid::tests::make_random_grid;
+
+    #[test]
+    fn can_parse_larger_than_life_rules() {
+        assert_eq!(Ok(LargerThanLife::bosco()), "R5,C0,M1,S34..58,B34..45,NM".parse());
+        assert_eq!(Ok(LargerThanLife::bosco()), "R5,C2,M1,S34..58,B34..45".parse());
+        assert_eq!(Ok(LargerThanLife::new(10, 3, false, 4..=4, 1..=9, Shape::Circular)),
+                   "r10,c3,m0,s4,b1..9,nc".parse());
+    }
+
+    #[test]
+    fn can_round_trip_display() {
+        for s in &["R5,C0,M1,S34..58,B34..45,NM", "R2,C5,M0,S1..3,B2..2,NN", "R10,C0,M0,S0..0,B1..1,NC"] {
+            let rule: LargerThanLife = s.parse().unwrap();
+            assert_eq!(&rule.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn fails_with_invalid_rulestrings() {
+        let cases = &[
+            ("",                           ParseError::InvalidFormat),
+            ("R5,C0,M1,S34..58",           ParseError::InvalidFormat),
+            ("R5,C0,M2,S34..58,B34..45",   ParseError::InvalidFormat),
+            ("R5,C0,M1,S58..34,B34..45",   ParseError::InvalidFormat),
+            ("R5,C0,M1,S34..58,B34..45,NX", ParseError::InvalidFormat),
+            ("R5,C0,M1,S34..58,B34..45,X", ParseError::InvalidFormat),
+            ("R5,C0,M1,S3a..58,B34..45",   ParseError::InvalidDigit('a')),
+            ("R0,C0,M1,S34..58,B34..45",   ParseError::InvalidRadius),
+            ("R501,C0,M1,S34..58,B34..45", ParseError::InvalidRadius),
+            ("R5,C257,M1,S34..58,B34..45", ParseError::InvalidStates),
+        ];
+        for &(s, ref expected) in cases {
+            assert_eq!(s.parse::<LargerThanLife>().as_ref(), Err(expected), "Parsing {:?}", s);
+        }
+    }
+
+    #[test]
+    fn can_measure_neighbourhood_sizes() {
+        let size = |r, shape| RangeNeighbours::new(r, shape, Edges::Torus).size();
+
+        assert_eq!(size(1, Shape::Moore), 8);
+        assert_eq!(size(5, Shape::Moore), 120);
+        assert_eq!(size(1, Shape::VonNeumann), 4);
+        assert_eq!(size(2, Shape::VonNeumann), 12);
+        assert_eq!(size(1, Shape::Circular), 8);
+        assert_eq!(size(2, Shape::Circular), 20);
+    }
+
+    #[test]
+    fn summed_counts_match_direct_counts() {
+        let grid = make_random_grid(23, 17, 7);
+        for &radius in &[1, 2, 5, 10, 12] {
+            for &shape in &[Shape::Moore, Shape::VonNeumann, Shape::Circular] {
+                for &edges in &[Edges::Torus, Edges::Terminal] {
+                    let neighbours = RangeNeighbours::new(radius, shape, edges);
+                    let direct: Vec<_> = grid.iter_cells()
+                        .map(|(x, y, _)| neighbours.count(&grid, x, y))
+                        .collect();
+                    assert_eq!(neighbours.count_all(&grid), Some(direct),
+                               "R{} {:?} {:?}", radius, shape, edges);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn range_one_rules_match_life_like_rules() {
+        let conway: LargerThanLife = "R1,C0,M1,S3..4,B3..3,NM".parse().unwrap();
+        assert_eq!(conway.life_like(), Some(LifeLike::conway()));
+
+        let mut expected = World::new(make_random_grid(20, 20, 3));
+        let mut actual = World::new(make_random_grid(20, 20, 3));
+        actual.set_rules(conway).unwrap();
+        actual.set_neighbours(RangeNeighbours::new(1, Shape::Moore, Edges::Torus)).unwrap();
+        for _ in 0..10 {
+            expected.step_mut();
+            actual.step_mut();
+            assert_eq!(actual.grid(), expected.grid());
+        }
+    }
+
+    #[test]
+    fn can_step_large_range_world() {
+        let rule = LargerThanLife::bosco();
+        let mut w = World::new(make_random_grid(40, 30, 11));
+        w.set_rules(rule).unwrap();
+        w.set_neighbours(rule.neighbourhood(Edges::Torus)).unwrap();
+        assert_eq!(rule.life_like(), None);
+
+        // Check each step against the rule applied to directly counted neighbours
+        let neighbours = rule.neighbourhood(Edges::Torus);
+        for _ in 0..3 {
+            let curr = w.grid().clone();
+            w.step_mut();
+            for (x, y, cell) in curr.iter_cells() {
+                let expected = rule.next_cell(cell, neighbours.count(&curr, x, y));
+                assert_eq!(Some(w.grid().cell_at(x, y)), expected.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn set_rules_applies_range_and_shape() {
+        let rule: LargerThanLife = "R3,C0,M0,S2..9,B5..7,NN".parse().unwrap();
+        let mut expected = World::new(make_random_grid(30, 30, 7));
+        expected.set_neighbours(rules::TerminalNeighbours).unwrap();
+        let mut actual = World::new(expected.grid().clone());
+        actual.set_neighbours(rules::TerminalNeighbours).unwrap();
+
+        actual.set_rules(rule).unwrap();
+        expected.set_neighbours(rule.neighbourhood(Edges::Terminal)).unwrap();
+        expected.set_rules(rule).unwrap();
+        for _ in 0..3 {
+            expected.step_mut();
+            actual.step_mut();
+            assert_eq!(actual.grid(), expected.grid());
+        }
+
+        assert_eq!(actual.set_neighbours(rules::TorusNeighbours), Err(RuleError::RangeMismatch));
+        assert_eq!(actual.set_neighbours(RangeNeighbours::new(3, Shape::Moore, Edges::Torus)),
+                   Err(RuleError::RangeMismatch));
+        assert_eq!(actual.set_neighbours(rule.neighbourhood(Edges::Torus)), Ok(()));
+    }
+}
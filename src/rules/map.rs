@@ -0,0 +1,209 @@
+//! Module for parsing [MAP](http://conwaylife.com/wiki/Non-isotropic_rule) rulestrings,
+//! which give the next state of every configuration of the 3x3 Moore neighbourhood
+
+use grid::Cell;
+use super::{ Rule, LifeLike, Isotropic, ParseError, CENTRE };
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The characters of the base64 alphabet, in order
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The number of base64 characters needed for the 512 bit table
+const LENGTH: usize = 86;
+
+/// Returns the table of next states of a life-like ruleset
+pub(crate) fn life_like_table(rule: LifeLike) -> [bool; 512] {
+    let mut table = [false; 512];
+    for (config, live) in table.iter_mut().enumerate() {
+        let neighbours = (config & !CENTRE).count_ones() as usize;
+        *live = if config & CENTRE != 0 { rule.is_survival(neighbours) } else { rule.is_birth(neighbours) };
+    }
+    table
+}
+
+/// Returns the life-like ruleset with the given table of next states, if the table depends
+/// only on the count of live neighbours
+pub(crate) fn table_life_like(table: &[bool; 512]) -> Option<LifeLike> {
+    let next = |centre| -> Vec<usize> {
+        (0..9).filter(|&n| {
+            let config = (0..512)
+                .find(|&c| c & CENTRE == centre && (c & !CENTRE).count_ones() as usize == n)
+                .unwrap();
+            table[config]
+        }).collect()
+    };
+    let rule = LifeLike::new(&next(0), &next(CENTRE));
+    if life_like_table(rule)[..] == table[..] { Some(rule) } else { None }
+}
+
+/// A ruleset given by the next state of every configuration of the 3x3 Moore
+/// neighbourhood, which need not be isotropic.
+///
+/// Rules are parsed from and displayed as `MAP` followed by the 512 bit table in base64,
+/// where the bit for each configuration is in the order given by `rules::configuration`.
+/// Any life-like or isotropic ruleset may be converted to a `Map`.
+///
+/// As with `Isotropic` rules, `World` uses the table when stepping with a Moore
+/// neighbourhood, and `next_cell` returns `None` unless the rule is life-like, so `World`
+/// rejects other neighbourhoods for rules which are not.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Map {
+    table: [bool; 512],
+    life_like: Option<LifeLike>,
+}
+
+impl Map {
+    /// Constructs the ruleset with the given next state of each configuration
+    pub fn new(table: [bool; 512]) -> Map {
+        Map { table, life_like: table_life_like(&table) }
+    }
+
+    /// Constructs the ruleset equivalent to `rule`, if it is applied with a Moore
+    /// neighbourhood and depends only on its 3x3 neighbourhood
+    pub fn from_rule<R>(rule: &R) -> Option<Map>
+        where R: Rule + ?Sized
+    {
+        rule.transitions().map(Map::new)
+    }
+
+    /// Returns true if a cell in `configuration` is live in the next generation.
+    ///
+    /// See `rules::configuration` for the layout of configurations.
+    #[inline]
+    pub fn is_live(&self, configuration: usize) -> bool {
+        self.table[configuration]
+    }
+}
+
+impl From<LifeLike> for Map {
+    fn from(rule: LifeLike) -> Map {
+        Map::new(life_like_table(rule))
+    }
+}
+
+impl From<Isotropic> for Map {
+    fn from(rule: Isotropic) -> Map {
+        Map::new(rule.transitions().unwrap())
+    }
+}
+
+impl Rule for Map {
+    /// Applies the rule to a count of neighbours, which is only possible if the rule is
+    /// life-like
+    ///
+    /// Returns `None` if the rule is not life-like.
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        self.life_like.and_then(|rule| rule.next_cell(cell, neighbours))
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        self.life_like
+    }
+
+    fn transitions(&self) -> Option<[bool; 512]> {
+        Some(self.table)
+    }
+}
+
+impl fmt::Display for Map {
+    /// Formats the rule as `MAP` followed by the table in base64, without padding
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MAP")?;
+        for chunk in self.table.chunks(6) {
+            let value = (0..6).fold(0, |value, i| value << 1 | chunk.get(i).map_or(0, |&b| b as usize));
+            write!(f, "{}", BASE64[value] as char)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Map {
+    type Err = ParseError;
+
+    /// Parses a rule in `MAP<base64>` notation, with or without `=` padding
+    fn from_str(s: &str) -> Result<Map, ParseError> {
+        let data = match s.trim().strip_prefix("MAP") {
+            Some(data) => data.trim_end_matches('='),
+            None       => return Err(ParseError::InvalidFormat),
+        };
+        if data.len() != LENGTH {
+            return Err(ParseError::InvalidFormat);
+        }
+        let mut table = [false; 512];
+        for (i, c) in data.chars().enumerate() {
+            let value = BASE64.iter().position(|&b| b as char == c).ok_or(ParseError::InvalidDigit(c))?;
+            for bit in (0..6).filter(|bit| i * 6 + bit < 512) {
+                table[i * 6 + bit] = value & (1 << (5 - bit)) != 0;
+            }
+        }
+        Ok(Map::new(table))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Map;
+    use rules::{ Rule, LifeLike, Isotropic, ParseError, StandardRules, OffsetNeighbours, Edges, RuleError };
+    use grid::Cell;
+    use world::World;
+    use bitgrid::tests::make_random_grid;
+
+    const CONWAY: &str = "MAPARYXfhZofugWaH7oaIDogBZofuhogOiAaIDogIAAgAAWaH7oaIDogGiA6ICAAIAAaIDogIAAgACAAIAAAAAAAA";
+
+    #[test]
+    fn can_convert_rules_to_map() {
+        assert_eq!(Map::from(LifeLike::conway()).to_string(), CONWAY);
+        assert_eq!(Map::from_rule(&StandardRules).unwrap().to_string(), CONWAY);
+
+        let rule: Isotropic = "B3/S23".parse().unwrap();
+        assert_eq!(Map::from(rule).to_string(), CONWAY);
+
+        let rule: Isotropic = "B2-a/S12".parse().unwrap();
+        let map: Map = Map::from(rule).to_string().parse().unwrap();
+        assert_eq!(map.transitions(), rule.transitions());
+        assert_eq!(map.life_like(), None);
+
+        assert_eq!(Map::from_rule(&|_: &_, _| ::grid::Cell::Dead), None);
+    }
+
+    #[test]
+    fn can_parse_map_rules() {
+        let map: Map = CONWAY.parse().unwrap();
+        assert_eq!(map.life_like(), Some(LifeLike::conway()));
+        assert_eq!(format!("{}==", CONWAY).parse(), Ok(map));
+
+        assert_eq!("ARYX".parse::<Map>(), Err(ParseError::InvalidFormat));
+        assert_eq!("MAPARYX".parse::<Map>(), Err(ParseError::InvalidFormat));
+        assert_eq!(CONWAY.replace('Y', "!").parse::<Map>(), Err(ParseError::InvalidDigit('!')));
+    }
+
+    #[test]
+    fn can_step_world_with_anisotropic_rules() {
+        // Each cell takes the state of its neighbour to the east, so the grid moves west
+        let mut table = [false; 512];
+        for (config, live) in table.iter_mut().enumerate() {
+            *live = config & 0b000_001_000 != 0;
+        }
+        let grid = make_random_grid(12, 9, 2);
+        let mut w = World::new(grid.clone());
+        w.set_rules(Map::new(table)).unwrap();
+        w.step_mut();
+
+        assert_eq!(w.grid(), &grid.translate(-1, 0));
+    }
+
+    #[test]
+    fn rejects_other_neighbourhoods() {
+        let rule: Isotropic = "B2-a/S12".parse().unwrap();
+        let mut w = World::new(make_random_grid(8, 8, 1));
+        w.set_neighbours(OffsetNeighbours::von_neumann(Edges::Torus)).unwrap();
+
+        assert_eq!(w.set_rules(Map::from(rule)), Err(RuleError::NeedsMooreNeighbourhood));
+        assert_eq!(Map::from(rule).next_cell(&Cell::Dead, 2), None);
+        assert_eq!(Map::from(LifeLike::conway()).next_cell(&Cell::Dead, 3), Some(Cell::Live));
+    }
+}
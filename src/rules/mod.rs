@@ -0,0 +1,520 @@
+//! Module containing traits and types implementing Game of Life rulesets and neighbourhoods.
+
+mod lifelike;
+mod generations;
+mod isotropic;
+mod ltl;
+mod map;
+mod offsets;
+mod summed;
+
+use grid::{ Grid, Cell };
+
+use std::fmt;
+use std::error;
+
+pub use self::lifelike::{ LifeLike, ParseResult };
+pub use self::generations::Generations;
+pub use self::isotropic::Isotropic;
+pub use self::ltl::{ LargerThanLife, RangeNeighbours, Shape };
+pub use self::map::Map;
+pub use self::offsets::OffsetNeighbours;
+
+/// Represents any errors which occur while parsing a rulestring of any family
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    InvalidFormat,
+    InvalidDigit(char),
+    InvalidStates,
+    InvalidRadius,
+    InvalidLetter(char),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ParseError::*;
+        match *self {
+            InvalidFormat    => write!(f, "Rulestring was not in B/S or S/B notation"),
+            InvalidDigit(c)  => write!(f, "Invalid neighbour count: {}", c),
+            InvalidStates    => write!(f, "State count was not between 2 and 256"),
+            InvalidRadius    => write!(f, "Range was not between 1 and 500"),
+            InvalidLetter(c) => write!(f, "Invalid neighbour arrangement: {}", c),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Describes why a ruleset could not be used with a neighbourhood
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RuleError {
+    /// The ruleset depends on the arrangement of neighbours, which is only known for the
+    /// Moore neighbourhood
+    NeedsMooreNeighbourhood,
+    /// The ruleset counts neighbours over its own `Rule::range`, which the neighbourhood
+    /// does not match
+    RangeMismatch,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::RuleError::*;
+        match *self {
+            NeedsMooreNeighbourhood => {
+                write!(f, "Rules which depend on the arrangement of neighbours need a Moore neighbourhood")
+            },
+            RangeMismatch => {
+                write!(f, "Neighbourhood does not match the range of the rules")
+            },
+        }
+    }
+}
+
+impl error::Error for RuleError {}
+
+/// Describes a ruleset.
+///
+/// A ruleset accepts a current cell state and the count of neighbours
+/// that cell has and returns a new cell state. Implementors may carry
+/// state, such as a parsed rulestring.
+pub trait Rule {
+    /// Returns the next state of `cell` given its count of live `neighbours`.
+    ///
+    /// Returns `None` if the count alone does not determine the next state, as for rules
+    /// which depend on the arrangement of neighbours. Such rules give `transitions`
+    /// instead.
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell>;
+
+    /// Returns the equivalent `LifeLike` ruleset, if there is one.
+    ///
+    /// Allows faster stepping strategies to be used for life-like rules.
+    fn life_like(&self) -> Option<LifeLike> {
+        None
+    }
+
+    /// Returns the number of states a cell may be in, including live and dead.
+    ///
+    /// Rules with more than two states also produce `Dying` cells.
+    fn states(&self) -> usize {
+        2
+    }
+
+    /// Returns whether a cell is live in the next generation for each of the 512
+    /// configurations of its 3x3 Moore neighbourhood, if the rule depends only on that
+    /// neighbourhood.
+    ///
+    /// Used in place of `next_cell` when the neighbourhood is the Moore neighbourhood,
+    /// so that every rule with a table shares the same stepping path. See `configuration`
+    /// for the layout of each configuration.
+    ///
+    /// By default the table is derived from the equivalent `LifeLike` ruleset, if there
+    /// is one.
+    fn transitions(&self) -> Option<[bool; 512]> {
+        self.life_like().map(map::life_like_table)
+    }
+
+    /// Returns the radius and `Shape` of the neighbourhood this ruleset counts neighbours
+    /// over, if it has its own, such as a `LargerThanLife` ruleset.
+    ///
+    /// `World::set_rules` replaces a neighbourhood of any other range with the matching
+    /// `RangeNeighbours`.
+    fn range(&self) -> Option<(usize, Shape)> {
+        None
+    }
+}
+
+impl<F> Rule for F where F: Fn(&Cell, usize) -> Cell {
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        Some(self(cell, neighbours))
+    }
+}
+
+/// Describes a static ruleset function.
+///
+/// This function accepts a current cell state and the count of neighbours
+/// that cell has and returns a new cell state.
+pub type RulesFn = fn(cell: &Cell, neighbours: usize) -> Cell;
+
+/// Implements the [standard rules](https://en.wikipedia.org/wiki/Conway%27s_Game_of_Life#Rules)
+/// of the Game of Life.
+#[derive(Clone, Copy, Debug)]
+pub struct StandardRules;
+
+impl Rule for StandardRules {
+    #[inline]
+    fn next_cell(&self, cell: &Cell, neighbours: usize) -> Option<Cell> {
+        Some(standard_rules(cell, neighbours))
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        Some(LifeLike::conway())
+    }
+}
+
+/// Implements the [standard rules](https://en.wikipedia.org/wiki/Conway%27s_Game_of_Life#Rules)
+/// of the Game of Life. Equivalent to `StandardRules`.
+pub fn standard_rules(cell: &Cell, neighbours: usize) -> Cell {
+    match (cell, neighbours) {
+        (&Cell::Live, 3) |
+        (&Cell::Live, 2) |
+        (&Cell::Dead, 3) => Cell::Live,
+        ________________ => Cell::Dead
+    }
+}
+
+
+/// Describes a neighbourhood.
+///
+/// A neighbourhood accepts a Grid and a set of coordinates and
+/// returns the neighbour count of those coordinates.
+pub trait Neighbourhood {
+    /// Returns the count of live neighbours of the cell at `(x, y)`
+    fn count(&self, grid: &Grid, x: usize, y: usize) -> usize;
+
+    /// Returns how the edges of the grid are treated, if this is the radius 1
+    /// [Moore neighbourhood](http://conwaylife.com/wiki/Moore_neighbourhood).
+    ///
+    /// Allows faster stepping strategies to be used for the Moore neighbourhood.
+    fn moore_edges(&self) -> Option<Edges> {
+        None
+    }
+
+    /// Returns how the edges of the grid are treated, if it is known.
+    ///
+    /// By default this is only known for the Moore neighbourhood.
+    fn edges(&self) -> Option<Edges> {
+        self.moore_edges()
+    }
+
+    /// Returns the radius and `Shape` of this neighbourhood, if it counts every cell of
+    /// that shape within the radius other than the centre.
+    ///
+    /// Allows rulesets with their own `Rule::range` to check that they are counted over it.
+    fn range(&self) -> Option<(usize, Shape)> {
+        self.moore_edges().map(|_| (1, Shape::Moore))
+    }
+
+    /// Returns the furthest distance, along either axis, at which a neighbour is counted,
+    /// if it is known.
+    ///
+    /// Allows stepping strategies which skip unchanged regions to be used.
+    fn radius(&self) -> Option<usize> {
+        self.moore_edges().map(|_| 1)
+    }
+
+    /// Returns the count of live neighbours of every cell in `grid`, in row order, if
+    /// they can be counted more efficiently together than one at a time.
+    ///
+    /// Allows neighbourhoods to share work between cells, e.g. with a summed-area table.
+    fn count_all(&self, _grid: &Grid) -> Option<Vec<usize>> {
+        None
+    }
+}
+
+/// Returns the configuration of the 3x3 square centred on the cell at `(x, y)`, used to
+/// index the table returned by `Rule::transitions`.
+///
+/// Each live cell sets one bit, from bit 8 for the top left cell through to bit 0 for the
+/// bottom right cell in rows from top to bottom, so the centre cell is bit 4.
+pub fn configuration(grid: &Grid, x: usize, y: usize, edges: Edges) -> usize {
+    let (w, h) = (grid.width() as isize, grid.height() as isize);
+    let mut config = 0;
+    for dy in -1..2 {
+        for dx in -1..2 {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            let live = match edges {
+                Edges::Torus    => grid.wrapping_cell_at(nx, ny).is_live(),
+                Edges::Terminal => {
+                    nx >= 0 && ny >= 0 && nx < w && ny < h &&
+                    grid.cell_at(nx as usize, ny as usize).is_live()
+                },
+            };
+            config = config << 1 | live as usize;
+        }
+    }
+    config
+}
+
+/// The bit of a configuration holding the centre cell
+pub(crate) const CENTRE: usize = 0b000_010_000;
+
+/// Describes how neighbours are counted beyond the edges of a grid
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Edges {
+    /// Opposite edges are joined, so cells beyond an edge wrap around to the other side
+    Torus,
+    /// Cells beyond the edges are dead
+    Terminal,
+}
+
+impl<F> Neighbourhood for F where F: Fn(&Grid, usize, usize) -> usize {
+    #[inline]
+    fn count(&self, grid: &Grid, x: usize, y: usize) -> usize {
+        self(grid, x, y)
+    }
+}
+
+/// Describes a static neighbour counting function.
+///
+/// This function accepts a Grid and a set of coordinates and
+/// and returns the neighbour count of those coordinates.
+pub type NeighboursFn = fn(grid: &Grid, x: usize, y: usize) -> usize;
+
+/// Implements neighbour counting for a torus world.
+#[derive(Clone, Copy, Debug)]
+pub struct TorusNeighbours;
+
+impl Neighbourhood for TorusNeighbours {
+    #[inline]
+    fn count(&self, grid: &Grid, x: usize, y: usize) -> usize {
+        let (w, h) = (grid.width(), grid.height());
+        let (left, right) = (offset_in_dim(w, x, -1), offset_in_dim(w, x, 1));
+        let (up, down) = (offset_in_dim(h, y, -1), offset_in_dim(h, y, 1));
+
+        [(left, up), (x, up), (right, up), (left, y), (right, y), (left, down), (x, down), (right, down)]
+            .iter()
+            .filter(|&&(x, y)| grid.cell_at(x, y).is_live())
+            .count()
+    }
+
+    fn moore_edges(&self) -> Option<Edges> {
+        Some(Edges::Torus)
+    }
+}
+
+/// Implements neighbour counting for a torus world. Equivalent to `TorusNeighbours`, which
+/// is preferred by `World::set_neighbours` as it is known to be the Moore neighbourhood.
+pub fn torus_neighbours(grid: &Grid, x: usize, y: usize) -> usize {
+    TorusNeighbours.count(grid, x, y)
+}
+
+/// Implements neighbour counting for a terminal world.
+#[derive(Clone, Copy, Debug)]
+pub struct TerminalNeighbours;
+
+impl Neighbourhood for TerminalNeighbours {
+    fn count(&self, grid: &Grid, x: usize, y: usize) -> usize {
+        #[derive(PartialEq)]
+        enum O { Prev, None, Next }
+
+        fn apply(o: &O, dim: usize) -> usize {
+            match o {
+                &O::Prev => dim - 1 ,
+                &O::None => dim ,
+                &O::Next => dim + 1
+            }
+        }
+
+        let offsets: &[O; 3] = &[O::Prev, O::None, O::Next];
+        let (w, h) = (grid.width(), grid.height());
+
+        let mut count = 0;
+
+        for y_off in offsets {
+            for x_off in offsets {
+
+                if *y_off == O::None && *x_off == O::None {
+                    //Don't count "this" cell
+                    continue;
+                }
+
+                let is_cell_out_of_range =
+                    y == 0       && *y_off == O::Prev ||
+                    y == (h - 1) && *y_off == O::Next ||
+                    x == 0       && *x_off == O::Prev ||
+                    x == (w - 1) && *x_off == O::Next;
+
+                if is_cell_out_of_range {
+                    //Count cell as dead
+                    continue;
+                }
+
+                let x = apply(x_off, x);
+                let y = apply(y_off, y);
+
+                if grid.cell_at(x, y).is_live() {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    fn moore_edges(&self) -> Option<Edges> {
+        Some(Edges::Terminal)
+    }
+}
+
+/// Implements neighbour counting for a terminal world. Equivalent to `TerminalNeighbours`,
+/// which is preferred by `World::set_neighbours` as it is known to be the Moore neighbourhood.
+pub fn terminal_neighbours(grid: &Grid, x: usize, y: usize) -> usize {
+    TerminalNeighbours.count(grid, x, y)
+}
+
+type Delta = isize;
+
+/// Utility function to calculate a new index within a torus dimension of `dimension_size`
+/// based on a `current_index` and a `delta`.
+fn offset_in_dim(dimension_size: usize, current_index: usize, delta: Delta) -> usize {
+
+    match delta {
+        n if n < 0 => {
+            //convert to unsigned representing a subtraction
+            let to_subtract = n.abs() as usize;
+
+            if current_index >= to_subtract {
+                current_index - to_subtract
+            }
+            else {
+                //wrap to end of dimension
+                dimension_size - (to_subtract - current_index)
+            }
+        },
+        0 => {
+            current_index
+        },
+        n if n > 0 => {
+            //convert to unsigned representing an addition
+            let to_add = n.abs() as usize;
+
+            let delta_to_end = dimension_size - current_index;
+            if delta_to_end > to_add {
+                current_index + to_add
+            }
+            else {
+                //wrap to beginning of dimension
+                to_add - delta_to_end
+            }
+        },
+        _ => {
+            panic!(format!("Unexpected delta: {}", delta))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ torus_neighbours, terminal_neighbours };
+
+    use grid::tests::{ make_square_grid,
+                       make_pipe_grid,
+                       make_oblong_grid,
+                       make_lonely_grid };
+
+
+    #[test]
+    fn can_count_torus_neighbours_on_square_grid() {
+        let g = make_square_grid();
+
+        let neighbours = torus_neighbours(&g, 1, 1);
+
+        assert_eq!(neighbours, 8);
+    }
+
+    #[test]
+    fn can_count_torus_neighbours_on_pipe_grid() {
+        let g = make_pipe_grid();
+
+        let neighbours = torus_neighbours(&g, 2, 1);
+        assert_eq!(neighbours, 3);
+
+        let neighbours = torus_neighbours(&g, 0, 1);
+        assert_eq!(neighbours, 3);
+    }
+
+    #[test]
+    fn can_count_torus_neighbours_on_lonely_grid() {
+        let g = make_lonely_grid();
+
+        let neighbours = torus_neighbours(&g, 1, 1);
+
+        assert_eq!(neighbours, 0);
+    }
+
+    #[test]
+    fn can_count_torus_neighbours_on_oblong_grid() {
+        let g = make_oblong_grid();
+
+        let neighbours = torus_neighbours(&g, 2, 1);
+        assert_eq!(neighbours, 4);
+
+        let neighbours = torus_neighbours(&g, 4, 2);
+        assert_eq!(neighbours, 1);
+
+        let neighbours = torus_neighbours(&g, 0, 0);
+        assert_eq!(neighbours, 1);
+    }
+
+    #[test]
+    fn can_count_terminal_neighbours_on_oblong_grid() {
+        let g = make_oblong_grid();
+
+        let neighbours = terminal_neighbours(&g, 0, 0);
+        assert_eq!(neighbours, 1);
+
+        let neighbours = terminal_neighbours(&g, 4, 2);
+        assert_eq!(neighbours, 1);
+
+        let neighbours = terminal_neighbours(&g, 2, 1);
+        assert_eq!(neighbours, 4);
+    }
+
+    #[test]
+    fn can_find_configurations() {
+        use super::{ configuration, Edges };
+
+        let g = make_oblong_grid();
+
+        assert_eq!(configuration(&g, 2, 1, Edges::Torus), 0b010_101_010);
+        assert_eq!(configuration(&g, 2, 0, Edges::Torus), 0b010_010_101);
+        assert_eq!(configuration(&g, 2, 0, Edges::Terminal), 0b000_010_101);
+    }
+
+    #[test]
+    fn standard_rules_match_conway_rulestring() {
+        use super::{ Rule, StandardRules, LifeLike };
+        use grid::Cell::{ Live, Dead };
+
+        let conway = LifeLike::conway();
+        for n in 0..9 {
+            for cell in &[Live, Dead] {
+                assert_eq!(StandardRules.next_cell(cell, n), conway.next_cell(cell, n));
+                assert_eq!(Some(super::standard_rules(cell, n)), conway.next_cell(cell, n));
+            }
+        }
+    }
+
+    #[test]
+    fn can_calculate_index() {
+        use super::{ Delta };
+
+        //Verify that offset_in_dim correctly wraps the world
+
+        //Middle of dimension
+        assert_eq!(super::offset_in_dim(10, 5, (6 as Delta)), 1);
+        assert_eq!(super::offset_in_dim(10, 5, (4 as Delta)), 9);
+        assert_eq!(super::offset_in_dim(10, 5, (1 as Delta)), 6);
+        assert_eq!(super::offset_in_dim(10, 5, (0 as Delta)), 5);
+        assert_eq!(super::offset_in_dim(10, 5, (-1 as Delta)), 4);
+        assert_eq!(super::offset_in_dim(10, 5, (-4 as Delta)), 1);
+        assert_eq!(super::offset_in_dim(10, 5, (-6 as Delta)), 9);
+
+        //End of dimension
+        assert_eq!(super::offset_in_dim(10, 9, (2 as Delta)), 1);
+        assert_eq!(super::offset_in_dim(10, 9, (1 as Delta)), 0);
+        assert_eq!(super::offset_in_dim(10, 9, (0 as Delta)), 9);
+        assert_eq!(super::offset_in_dim(10, 9, (-1 as Delta)), 8);
+        assert_eq!(super::offset_in_dim(10, 9, (-2 as Delta)), 7);
+
+        //Start of dimension
+        assert_eq!(super::offset_in_dim(10, 0, (2 as Delta)), 2);
+        assert_eq!(super::offset_in_dim(10, 0, (1 as Delta)), 1);
+        assert_eq!(super::offset_in_dim(10, 0, (0 as Delta)),    0);
+        assert_eq!(super::offset_in_dim(10, 0, (-1 as Delta)), 9);
+        assert_eq!(super::offset_in_dim(10, 0, (-2 as Delta)), 8);
+    }
+
+}
\ No newline at end of file
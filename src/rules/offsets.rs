@@ -0,0 +1,154 @@
+//! Module for neighbourhoods described by an arbitrary list of offsets, such as the
+//! [von Neumann neighbourhood](http://conwaylife.com/wiki/Von_Neumann_neighbourhood)
+
+use grid::Grid;
+use super::{ Neighbourhood, Edges };
+
+/// The offsets of the radius 1 Moore neighbourhood, in sorted order
+const MOORE: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+/// Counts the live cells at each of a set of `(dx, dy)` offsets from a cell.
+///
+/// Offsets may include `(0, 0)` to count the cell itself, and any repeated offsets are
+/// only counted once.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct OffsetNeighbours {
+    offsets: Vec<(isize, isize)>,
+    edges: Edges,
+}
+
+impl OffsetNeighbours {
+    /// Constructs a neighbourhood from a list of `(dx, dy)` offsets
+    pub fn new(offsets: &[(isize, isize)], edges: Edges) -> OffsetNeighbours {
+        let mut offsets = offsets.to_vec();
+        offsets.sort_unstable();
+        offsets.dedup();
+        OffsetNeighbours { offsets, edges }
+    }
+
+    /// Constructs the four cell von Neumann neighbourhood
+    pub fn von_neumann(edges: Edges) -> OffsetNeighbours {
+        OffsetNeighbours::new(&[(0, -1), (-1, 0), (1, 0), (0, 1)], edges)
+    }
+
+    /// Constructs a six cell neighbourhood which emulates a hexagonal grid, where each row
+    /// is shifted half a cell to the left of the row below it
+    pub fn hexagonal(edges: Edges) -> OffsetNeighbours {
+        OffsetNeighbours::new(&[(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)], edges)
+    }
+
+    /// Constructs the eight cell neighbourhood reached by a chess knight's move
+    pub fn knight(edges: Edges) -> OffsetNeighbours {
+        OffsetNeighbours::new(&[(-1, -2), (1, -2), (-2, -1), (2, -1),
+                                (-2, 1), (2, 1), (-1, 2), (1, 2)], edges)
+    }
+
+    /// Returns the distinct offsets counted, in sorted order
+    pub fn offsets(&self) -> &[(isize, isize)] {
+        &self.offsets
+    }
+}
+
+impl Neighbourhood for OffsetNeighbours {
+    fn count(&self, grid: &Grid, x: usize, y: usize) -> usize {
+        let (w, h) = (grid.width() as isize, grid.height() as isize);
+        self.offsets
+            .iter()
+            .filter(|&&(dx, dy)| {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                match self.edges {
+                    Edges::Torus    => grid.wrapping_cell_at(nx, ny).is_live(),
+                    Edges::Terminal => {
+                        nx >= 0 && ny >= 0 && nx < w && ny < h &&
+                        grid.cell_at(nx as usize, ny as usize).is_live()
+                    },
+                }
+            })
+            .count()
+    }
+
+    fn moore_edges(&self) -> Option<Edges> {
+        if self.offsets[..] == MOORE[..] { Some(self.edges) } else { None }
+    }
+
+    fn edges(&self) -> Option<Edges> {
+        Some(self.edges)
+    }
+
+    fn radius(&self) -> Option<usize> {
+        let radius = self.offsets
+            .iter()
+            .map(|&(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()))
+            .max();
+        Some(radius.unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::OffsetNeighbours;
+    use rules::{ self, Neighbourhood, Edges };
+    use world::World;
+    use grid::tests::make_oblong_grid;
+    use bitgrid::tests::make_random_grid;
+
+    #[test]
+    fn can_count_von_neumann_neighbours() {
+        let g = make_oblong_grid();
+        let torus = OffsetNeighbours::von_neumann(Edges::Torus);
+        let terminal = OffsetNeighbours::von_neumann(Edges::Terminal);
+
+        assert_eq!(torus.count(&g, 2, 1), 4);
+        assert_eq!(torus.count(&g, 1, 0), 2);
+        assert_eq!(torus.count(&g, 2, 0), 1);
+        assert_eq!(terminal.count(&g, 2, 0), 0);
+        assert_eq!(terminal.radius(), Some(1));
+        assert_eq!(terminal.moore_edges(), None);
+    }
+
+    #[test]
+    fn can_count_custom_neighbours() {
+        let g = make_oblong_grid();
+
+        assert_eq!(OffsetNeighbours::knight(Edges::Terminal).count(&g, 0, 1), 2);
+        assert_eq!(OffsetNeighbours::knight(Edges::Terminal).radius(), Some(2));
+        assert_eq!(OffsetNeighbours::hexagonal(Edges::Terminal).count(&g, 2, 1), 4);
+        assert_eq!(OffsetNeighbours::new(&[(0, 0), (0, 0)], Edges::Torus).count(&g, 1, 1), 1);
+        assert_eq!(OffsetNeighbours::new(&[], Edges::Torus).count(&g, 1, 1), 0);
+    }
+
+    #[test]
+    fn moore_offsets_match_moore_neighbourhood() {
+        let offsets: Vec<_> = (-1..2)
+            .flat_map(|dy| (-1..2).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .collect();
+        let torus = OffsetNeighbours::new(&offsets, Edges::Torus);
+        let terminal = OffsetNeighbours::new(&offsets, Edges::Terminal);
+        assert_eq!(torus.moore_edges(), Some(Edges::Torus));
+
+        let g = make_random_grid(9, 7, 5);
+        for (x, y, _) in g.iter_cells() {
+            assert_eq!(torus.count(&g, x, y), rules::torus_neighbours(&g, x, y));
+            assert_eq!(terminal.count(&g, x, y), rules::terminal_neighbours(&g, x, y));
+        }
+    }
+
+    #[test]
+    fn can_step_world_with_von_neumann_neighbours() {
+        use grid::Grid;
+        use grid::Cell::{ Live, Dead };
+
+        // Cells are born with exactly one neighbour and never survive
+        let mut w = World::new(Grid::from_fn(5, 5, |x, y| if (x, y) == (2, 2) { Live } else { Dead }));
+        w.set_rules(|cell: &_, n| if *cell == Dead && n == 1 { Live } else { Dead }).unwrap();
+        w.set_neighbours(OffsetNeighbours::von_neumann(Edges::Torus)).unwrap();
+        w.step_mut();
+
+        let expected = Grid::from_fn(5, 5, |x, y| {
+            if (x as isize - 2).abs() + (y as isize - 2).abs() == 1 { Live } else { Dead }
+        });
+        assert_eq!(w.grid(), &expected);
+    }
+}
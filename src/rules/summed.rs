@@ -0,0 +1,72 @@
+//! A [summed-area table](https://en.wikipedia.org/wiki/Summed-area_table) of live cells,
+//! used to count large neighbourhoods in constant time per rectangle.
+
+use grid::Grid;
+use super::Edges;
+
+/// Holds the number of live cells above and to the left of each point of a grid which has
+/// been extended by a margin on every side
+pub struct SummedAreaTable {
+    margin: isize,
+    stride: usize,
+    sums: Vec<usize>,
+}
+
+impl SummedAreaTable {
+    /// Builds the table for `grid`, extended by `margin` cells on every side. Cells in the
+    /// margin wrap around to the opposite edge on a torus, and are dead otherwise.
+    pub fn new(grid: &Grid, margin: usize, edges: Edges) -> SummedAreaTable {
+        let (w, h) = (grid.width() + 2 * margin, grid.height() + 2 * margin);
+        let stride = w + 1;
+        let mut sums = vec![0; stride * (h + 1)];
+        let m = margin as isize;
+        for y in 0..h {
+            let mut row = 0;
+            for x in 0..w {
+                let (gx, gy) = (x as isize - m, y as isize - m);
+                let live = match edges {
+                    Edges::Torus => grid.wrapping_cell_at(gx, gy).is_live(),
+                    Edges::Terminal => {
+                        gx >= 0 && gy >= 0 &&
+                        (gx as usize) < grid.width() && (gy as usize) < grid.height() &&
+                        grid.cell_at(gx as usize, gy as usize).is_live()
+                    },
+                };
+                row += live as usize;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+            }
+        }
+        SummedAreaTable { margin: m, stride, sums }
+    }
+
+    /// Returns the number of live cells in columns `x0..=x1` of rows `y0..=y1`, where the
+    /// coordinates may extend beyond the grid by up to the margin
+    #[inline]
+    pub fn count(&self, x0: isize, y0: isize, x1: isize, y1: isize) -> usize {
+        let index = |x: isize, y: isize| ((y + self.margin) as usize) * self.stride + (x + self.margin) as usize;
+        self.sums[index(x1 + 1, y1 + 1)] + self.sums[index(x0, y0)]
+            - self.sums[index(x0, y1 + 1)] - self.sums[index(x1 + 1, y0)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::SummedAreaTable;
+    use rules::Edges;
+    use grid::tests::make_glider_grid;
+
+    #[test]
+    fn can_count_rectangles() {
+        let grid = make_glider_grid();
+
+        let torus = SummedAreaTable::new(&grid, 2, Edges::Torus);
+        assert_eq!(torus.count(0, 0, 5, 4), 5);
+        assert_eq!(torus.count(2, 2, 3, 3), 3);
+        assert_eq!(torus.count(-2, -2, 7, 6), 9);
+
+        let terminal = SummedAreaTable::new(&grid, 2, Edges::Terminal);
+        assert_eq!(terminal.count(-2, -2, 7, 6), 5);
+        assert_eq!(terminal.count(-2, -2, -1, 6), 0);
+    }
+}
@@ -0,0 +1,150 @@
+//! Structures used for recording statistics about each generation of a `World`.
+
+use std::io::{ self, Write };
+
+use grid::Grid;
+
+/// Statistics describing a single generation
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct GenerationStats {
+    /// The generation described
+    pub generation: i64,
+    /// The number of live cells
+    pub population: usize,
+    /// The number of cells born since the previous generation
+    pub births: usize,
+    /// The number of cells which died since the previous generation
+    pub deaths: usize,
+    /// The bounds `(x, y, width, height)` of the live cells, if there are any
+    pub bounds: Option<(usize, usize, usize, usize)>,
+}
+
+/// Records the statistics of a series of generations
+#[derive(Clone, Debug, Default)]
+pub struct StatsRecorder {
+    entries: Vec<GenerationStats>,
+}
+
+impl StatsRecorder {
+    /// Constructs a new, empty `StatsRecorder`
+    pub fn new() -> StatsRecorder {
+        StatsRecorder { entries: Vec::new() }
+    }
+
+    /// Records the statistics of `grid` at `generation`, given the number of cells born and
+    /// the number which died since the previous generation
+    pub fn record(&mut self, generation: i64, births: usize, deaths: usize, grid: &Grid) {
+        self.entries.push(GenerationStats {
+            generation,
+            population: grid.population(),
+            births,
+            deaths,
+            bounds: grid.live_bounds(),
+        });
+    }
+
+    /// Returns the recorded statistics, oldest first
+    pub fn entries(&self) -> &[GenerationStats] {
+        &self.entries
+    }
+
+    /// Discards the statistics recorded after `generation`
+    pub fn truncate(&mut self, generation: i64) {
+        self.entries.retain(|entry| entry.generation <= generation);
+    }
+
+    /// Discards every recorded generation
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Writes the recorded statistics as CSV, with a header row.
+    ///
+    /// The bounds columns are left empty for generations with no live cells.
+    pub fn write_csv<W>(&self, mut writer: W) -> io::Result<()>
+        where W: Write
+    {
+        writeln!(writer, "generation,population,births,deaths,x,y,width,height")?;
+        for entry in &self.entries {
+            write!(writer, "{},{},{},{},", entry.generation, entry.population, entry.births, entry.deaths)?;
+            match entry.bounds {
+                Some((x, y, w, h)) => writeln!(writer, "{},{},{},{}", x, y, w, h)?,
+                None               => writeln!(writer, ",,,")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the cells born and the cells which died between `prev` and `next`
+pub(crate) fn count_changes(prev: &Grid, next: &Grid) -> (usize, usize) {
+    prev.iter_cells()
+        .zip(next.iter_cells())
+        .fold((0, 0), |(births, deaths), ((_, _, old), (_, _, new))| {
+            match (old.is_live(), new.is_live()) {
+                (false, true) => (births + 1, deaths),
+                (true, false) => (births, deaths + 1),
+                _             => (births, deaths),
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::StatsRecorder;
+    use rules;
+    use world::World;
+    use grid::Grid;
+    use grid::tests as grid_test;
+
+    #[test]
+    fn can_record_world_statistics() {
+        let mut w = World::new(grid_test::make_glider_grid());
+        w.set_recording(true);
+        w.step_mut();
+        w.step_mut();
+
+        let stats: Vec<_> = w.statistics().unwrap().entries().iter()
+            .map(|s| (s.generation, s.population, s.births, s.deaths, s.bounds))
+            .collect();
+
+        assert_eq!(stats, vec![(0, 5, 0, 0, Some((1, 1, 3, 3))),
+                               (1, 5, 2, 2, Some((2, 1, 3, 3))),
+                               (2, 5, 2, 2, Some((2, 1, 3, 3)))]);
+    }
+
+    #[test]
+    fn backends_record_same_statistics() {
+        use world::Backend;
+
+        let mut cells = World::new(grid_test::make_glider_grid());
+        let mut packed = World::new(grid_test::make_glider_grid());
+        packed.set_backend(Backend::BitPacked);
+        for w in &mut [&mut cells, &mut packed] {
+            w.set_neighbours(rules::TerminalNeighbours).unwrap();
+            w.set_recording(true);
+            for _ in 0..10 {
+                w.step_mut();
+            }
+        }
+
+        assert_eq!(cells.statistics().unwrap().entries(), packed.statistics().unwrap().entries());
+        assert!(cells.step().statistics().is_none());
+    }
+
+    #[test]
+    fn can_write_csv() {
+        let mut recorder = StatsRecorder::new();
+        recorder.record(0, 0, 0, &grid_test::make_lonely_grid());
+        recorder.record(1, 0, 1, &Grid::create_dead(3, 3));
+
+        let mut csv = Vec::new();
+        recorder.write_csv(&mut csv).unwrap();
+
+        assert_eq!(String::from_utf8(csv).unwrap(),
+                   "generation,population,births,deaths,x,y,width,height\n\
+                    0,1,0,0,1,1,1,1\n\
+                    1,0,0,1,,,,\n");
+    }
+}
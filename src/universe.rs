@@ -0,0 +1,277 @@
+//! Structures and functions used for constructing an unbounded Game of Life `Universe`.
+//!
+//! Unlike a `World`, which is limited to the fixed dimensions of its `Grid`, a `Universe`
+//! stores only its live cells and so grows automatically in every direction.
+
+use std::collections::{ HashMap, HashSet };
+use std::sync::Arc;
+
+use grid::{ Grid, Cell };
+use rules::{ Rule, StandardRules };
+
+/// Provides hosting for a Game of Life simulation on an infinite plane addressed by
+/// signed coordinates. Includes functions for modifying the universe and stepping the
+/// simulation both immutably and in-place.
+pub struct Universe {
+    gen: i64,
+    rules: Arc<dyn Rule + Send + Sync>,
+    cells: HashSet<(i64, i64)>,
+}
+
+impl Universe {
+
+    /// Constructs a new, empty `Universe`
+    pub fn new() -> Universe {
+        Universe { gen: 0, rules: Arc::new(StandardRules), cells: HashSet::new() }
+    }
+
+    /// Constructs a new `Universe` containing `grid`, with its top left cell at `(0, 0)`
+    pub fn from_grid(grid: &Grid) -> Universe {
+        let mut universe = Universe::new();
+        universe.write_cells(0, 0, grid);
+        universe
+    }
+
+    /// Sets the ruleset
+    ///
+    /// Panics if the ruleset causes dead cells with no neighbours to be born,
+    /// as this would fill the infinite plane, if it has more than two states or if it
+    /// depends on the arrangement of neighbours.
+    pub fn set_rules<R>(&mut self, rules: R)
+        where R: Rule + Send + Sync + 'static
+    {
+        assert!(rules.states() == 2, "Rules with dying states are not supported");
+        assert!(rules.transitions().is_none() || rules.life_like().is_some(),
+                "Rules which depend on the arrangement of neighbours are not supported");
+        assert!(rules.next_cell(&Cell::Dead, 0).is_some_and(|c| c.is_dead()),
+                "Rules which give birth to cells with no neighbours are not supported");
+        self.rules = Arc::new(rules);
+    }
+
+    /// Gets the current generation for this `Universe`
+    #[inline]
+    pub fn generation(&self) -> i64 {
+        self.gen
+    }
+
+    /// Gets the number of live cells in this `Universe`
+    #[inline]
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns the `Cell` at the given coordinates
+    pub fn cell_at(&self, x: i64, y: i64) -> Cell {
+        if self.cells.contains(&(x, y)) { Cell::Live } else { Cell::Dead }
+    }
+
+    /// Overwrites the `Cell` at the given coordinates with the given value
+    pub fn set_cell(&mut self, x: i64, y: i64, cell: Cell) {
+        if cell.is_live() {
+            self.cells.insert((x, y));
+        }
+        else {
+            self.cells.remove(&(x, y));
+        }
+    }
+
+    /// Overwrite the cells starting at coords `(x, y)` with the data in the given `Grid`
+    pub fn write_cells(&mut self, x: i64, y: i64, data: &Grid) {
+        for (data_x, data_y, cell) in data.iter_cells() {
+            self.set_cell(x + data_x as i64, y + data_y as i64, cell.clone());
+        }
+    }
+
+    /// Returns an iterator over the coordinates of every live cell, in no particular order
+    pub fn iter_live(&self) -> LiveIter<'_> {
+        LiveIter { inner: self.cells.iter() }
+    }
+
+    /// Returns the bounds `(x, y, width, height)` of the smallest region containing
+    /// every live cell, or `None` if the universe is empty
+    pub fn bounds(&self) -> Option<(i64, i64, usize, usize)> {
+        let mut iter = self.cells.iter();
+        let &(x, y) = iter.next()?;
+        let (x0, y0, x1, y1) = iter.fold((x, y, x, y), |(x0, y0, x1, y1), &(x, y)| {
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        });
+        Some((x0, y0, (x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize))
+    }
+
+    /// Returns the `width` by `height` region of this `Universe` whose top left cell
+    /// is at `(x, y)` as a `Grid`
+    pub fn to_grid(&self, x: i64, y: i64, width: usize, height: usize) -> Grid {
+        let mut grid = Grid::create_dead(width, height);
+        for &(cx, cy) in &self.cells {
+            let (gx, gy) = (cx - x, cy - y);
+            if gx >= 0 && gy >= 0 && gx < width as i64 && gy < height as i64 {
+                grid.set_cell(gx as usize, gy as usize, Cell::Live);
+            }
+        }
+        grid
+    }
+
+    /// Generates the set of live cells in the next generation
+    fn next_cells(&self) -> HashSet<(i64, i64)> {
+        //Count the live neighbours of every cell adjacent to a live cell
+        let mut counts: HashMap<(i64, i64), usize> = HashMap::with_capacity(self.cells.len() * 8);
+        for &(x, y) in &self.cells {
+            counts.entry((x, y)).or_insert(0);
+            for dy in -1..2 {
+                for dx in -1..2 {
+                    if (dx, dy) != (0, 0) {
+                        *counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        counts
+            .into_iter()
+            .filter(|&(pos, neighbours)| {
+                let cell = if self.cells.contains(&pos) { Cell::Live } else { Cell::Dead };
+                self.rules.next_cell(&cell, neighbours).is_some_and(|c| c.is_live())
+            })
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// Executes a single step of this `Universe` in place
+    pub fn step_mut(&mut self) {
+        self.cells = self.next_cells();
+        self.gen += 1;
+    }
+
+    /// Executes a single step of this `Universe` and returns a new, modified universe
+    pub fn step(&self) -> Universe {
+        Universe { gen: self.gen + 1,
+                   rules: self.rules.clone(),
+                   cells: self.next_cells() }
+    }
+}
+
+impl Default for Universe {
+    fn default() -> Universe {
+        Universe::new()
+    }
+}
+
+/// Iterator for the coordinates of live cells in a `Universe`
+pub struct LiveIter<'a> {
+    inner: ::std::collections::hash_set::Iter<'a, (i64, i64)>,
+}
+
+impl<'a> Iterator for LiveIter<'a> {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<(i64, i64)> {
+        self.inner.next().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Universe;
+    use rules;
+    use world::World;
+    use grid::Grid;
+    use grid::Cell::{ Live, Dead };
+    use grid::tests as grid_test;
+
+    #[test]
+    fn can_create_universe_from_grid() {
+        let grid = grid_test::make_glider_grid();
+        let u = Universe::from_grid(&grid);
+
+        assert_eq!(u.generation(), 0);
+        assert_eq!(u.population(), 5);
+        assert_eq!(u.bounds(), Some((1, 1, 3, 3)));
+        assert_eq!(u.to_grid(0, 0, 6, 5), grid);
+        assert_eq!(Universe::new().bounds(), None);
+    }
+
+    #[test]
+    fn can_get_and_set_cells_at_negative_coordinates() {
+        let mut u = Universe::new();
+
+        u.set_cell(-5, -7, Live);
+        assert_eq!(u.cell_at(-5, -7), Live);
+        assert_eq!(u.cell_at(5, 7), Dead);
+        assert_eq!(u.to_grid(-6, -7, 2, 1), Grid::from_raw(2, 1, vec![Dead, Live]));
+
+        u.set_cell(-5, -7, Dead);
+        assert_eq!(u.population(), 0);
+    }
+
+    #[test]
+    fn matches_world_away_from_edges() {
+        let mut grid = Grid::create_dead(20, 20);
+        grid.write_cells(8, 8, &grid_test::make_oblong_grid());
+        grid.write_cells(2, 2, &grid_test::make_glider_grid());
+
+        let mut world = World::new(grid.clone());
+        world.set_neighbours(rules::terminal_neighbours).unwrap();
+        let mut u = Universe::from_grid(&grid);
+
+        for _ in 0..8 {
+            world.step_mut();
+            u = u.step();
+            assert_eq!(&u.to_grid(0, 0, 20, 20), world.grid());
+        }
+        assert_eq!(u.generation(), 8);
+    }
+
+    #[test]
+    fn glider_travels_without_wrapping() {
+        let glider = Grid::from_raw(3, 3, vec![
+            Dead, Live, Dead,
+            Dead, Dead, Live,
+            Live, Live, Live,
+        ]);
+        let mut u = Universe::from_grid(&glider);
+
+        for _ in 0..400 {
+            u.step_mut();
+        }
+
+        assert_eq!(u.population(), 5);
+        assert_eq!(u.bounds(), Some((100, 100, 3, 3)));
+        assert_eq!(u.to_grid(100, 100, 3, 3), glider);
+    }
+
+    #[test]
+    fn can_step_with_other_rules() {
+        let mut u = Universe::new();
+        u.set_rules(rules::LifeLike::new(&[2], &[]));
+        u.set_cell(0, 0, Live);
+        u.set_cell(1, 0, Live);
+
+        u.step_mut();
+
+        let mut live: Vec<_> = u.iter_live().collect();
+        live.sort();
+        assert_eq!(live, vec![(0, -1), (0, 1), (1, -1), (1, 1)]);
+    }
+
+    #[test]
+    fn can_step_universe_on_another_thread() {
+        use std::thread;
+
+        let mut u = Universe::from_grid(&grid_test::make_glider_grid());
+        u.set_rules(rules::LifeLike::conway());
+        let u = thread::spawn(move || {
+            u.step_mut();
+            u
+        }).join().unwrap();
+
+        assert_eq!(u.generation(), 1);
+        assert_eq!(u.population(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "not supported")]
+    fn b0_rules_panic() {
+        Universe::new().set_rules(rules::LifeLike::new(&[0, 3], &[2, 3]));
+    }
+}
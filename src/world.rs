@@ -3,19 +3,82 @@
 //! Implements the functionality required for a simple Game of Life simulation.
 
 use std::iter::Iterator;
+use std::sync::Arc;
+
+use grid::{ Grid, Cell };
+use rules::{ self, Rule, Neighbourhood, StandardRules, TorusNeighbours, LifeLike, Edges, RangeNeighbours, RuleError };
+use bitgrid::BitGrid;
+use bands::for_each_band;
+use stats::{ self, StatsRecorder };
+use history::History;
+
+pub use history::HistoryMode;
+
+/// A ruleset which may be shared between stepping threads, applied a row at a time.
+///
+/// `next_row` is compiled for each ruleset type, so within a row every cell is stepped by
+/// a direct call to that ruleset's `next_cell`.
+trait RowRule: Rule + Send + Sync {
+    /// Writes the next state of each of `cells`, given its count of live `neighbours`,
+    /// into `next`
+    fn next_row(&self, cells: &[Cell], neighbours: &[usize], next: &mut [Cell]);
+}
+
+impl<R> RowRule for R where R: Rule + Send + Sync {
+    fn next_row(&self, cells: &[Cell], neighbours: &[usize], next: &mut [Cell]) {
+        for ((cell, &neighbours), new_cell) in cells.iter().zip(neighbours).zip(next) {
+            // Rules without a next state for a count are stepped with their transitions,
+            // which `check_arrangement` ensures
+            *new_cell = self.next_cell(cell, neighbours).unwrap_or(Cell::Dead);
+        }
+    }
+}
 
-use grid::{ Grid };
-use rules::{ RulesFn, NeighboursFn };
-use rules;
+/// A neighbourhood which may be shared between stepping threads, counted a row at a time.
+///
+/// `count_row` is compiled for each neighbourhood type, so within a row every cell is
+/// counted by a direct call to that neighbourhood's `count`.
+trait RowNeighbourhood: Neighbourhood + Send + Sync {
+    /// Writes the count of live neighbours of each cell in row `y` of `grid`, starting
+    /// at column `x`, into `counts`
+    fn count_row(&self, grid: &Grid, x: usize, y: usize, counts: &mut [usize]);
+}
+
+impl<N> RowNeighbourhood for N where N: Neighbourhood + Send + Sync {
+    fn count_row(&self, grid: &Grid, x: usize, y: usize, counts: &mut [usize]) {
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count = self.count(grid, x + i, y);
+        }
+    }
+}
+
+/// Selects how a `World` computes each generation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    /// Counts the neighbours of each `Cell` and applies the rules individually
+    Cells,
+    /// Steps 64 cells at a time using a bit-packed copy of the grid.
+    ///
+    /// Only used when the rules are life-like and the neighbourhood is the Moore
+    /// neighbourhood (e.g. `StandardRules` with `TorusNeighbours`), otherwise
+    /// stepping falls back to `Cells`.
+    BitPacked,
+}
 
 /// Provides hosting for a basic Game of Life simulation. Includes functions for modifying
 /// the world and stepping the simulation both immutably and in-place.
 pub struct World {
     gen: i64,
-    rules: RulesFn,
-    neighbours: NeighboursFn,
+    rules: Arc<dyn RowRule>,
+    neighbours: Arc<dyn RowNeighbourhood>,
     curr: Grid,
     prev: Option<Grid>,
+    backend: Backend,
+    packed: Option<(BitGrid, BitGrid)>,
+    threads: usize,
+    tiles: Option<ActiveTiles>,
+    stats: Option<StatsRecorder>,
+    history: Option<History>,
 }
 
 impl World {
@@ -23,18 +86,224 @@ impl World {
     /// Constructs a new `World` with the given `Grid`
     pub fn new(grid: Grid) -> World {
         World { gen: 0,
-                rules: rules::standard_rules, neighbours: rules::torus_neighbours,
-                curr: grid, prev: None  }
+                rules: Arc::new(StandardRules), neighbours: Arc::new(TorusNeighbours),
+                curr: grid, prev: None,
+                backend: Backend::Cells, packed: None, threads: 1, tiles: None, stats: None, history: None }
+    }
+
+    /// Sets the ruleset
+    ///
+    /// If the ruleset counts neighbours over its own `Rule::range`, such as a
+    /// `rules::LargerThanLife` ruleset, the neighbourhood is replaced by a
+    /// `rules::RangeNeighbours` of that range unless it already matches, keeping how the
+    /// edges of the grid are treated where that is known.
+    ///
+    /// Fails, leaving the ruleset unchanged, if the ruleset depends on the arrangement of
+    /// neighbours, such as a non-totalistic `rules::Isotropic` ruleset, and the
+    /// neighbourhood is not the Moore neighbourhood.
+    pub fn set_rules<R>(&mut self, rules: R) -> Result<(), RuleError>
+        where R: Rule + Send + Sync + 'static
+    {
+        // Rules with their own range bring a neighbourhood of that range
+        let ranged = match rules.range() {
+            Some((radius, shape)) if self.neighbours.range() != Some((radius, shape)) => {
+                let edges = self.neighbours.edges().unwrap_or(Edges::Torus);
+                Some(RangeNeighbours::new(radius, shape, edges))
+            },
+            _ => None,
+        };
+        match ranged {
+            Some(ref neighbours) => check_arrangement(&rules, neighbours)?,
+            None                 => check_arrangement(&rules, &*self.neighbours)?,
+        }
+        self.rules = Arc::new(rules);
+        if let Some(neighbours) = ranged {
+            self.neighbours = Arc::new(neighbours);
+        }
+        self.reset_tiles();
+        Ok(())
+    }
+
+    /// Sets the neighbourhood
+    ///
+    /// Fails, leaving the neighbourhood unchanged, if the ruleset depends on the
+    /// arrangement of neighbours and the neighbourhood is not the Moore neighbourhood, or
+    /// if the ruleset has its own `Rule::range` and the neighbourhood does not match it.
+    pub fn set_neighbours<N>(&mut self, neighbours: N) -> Result<(), RuleError>
+        where N: Neighbourhood + Send + Sync + 'static
+    {
+        check_arrangement(&*self.rules, &neighbours)?;
+        if self.rules.range().is_some_and(|range| neighbours.range() != Some(range)) {
+            return Err(RuleError::RangeMismatch);
+        }
+        self.neighbours = Arc::new(neighbours);
+        self.reset_tiles();
+        Ok(())
+    }
+
+    /// Sets the backend used to compute each generation
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+        self.packed = None;
+    }
+
+    /// Gets the backend used to compute each generation
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Sets the number of threads used to compute each generation.
+    ///
+    /// The grid is split into horizontal bands of rows which are stepped in parallel.
+    /// The default of `1` steps the whole grid on the calling thread.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Gets the number of threads used to compute each generation
+    #[inline]
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Enables or disables tracking of active regions.
+    ///
+    /// When enabled with `Some(tile_size)` the grid is divided into square tiles and each
+    /// step only recomputes the tiles which changed in the previous generation, and the
+    /// tiles adjacent to them. All other tiles are known to be stable and are skipped.
+    ///
+    /// Tracking only applies to the `Cells` backend, and to neighbourhoods whose `radius`
+    /// is known and no larger than `tile_size`. Otherwise every cell is recomputed.
+    pub fn set_active_tracking(&mut self, tile_size: Option<usize>) {
+        self.tiles = tile_size.map(|size| ActiveTiles::new(size.max(1), self.width(), self.height()));
+    }
+
+    /// Returns the regions `(x, y, width, height)` which will be recomputed by the next step,
+    /// or `None` if active region tracking is disabled
+    pub fn active_regions(&self) -> Option<Vec<(usize, usize, usize, usize)>> {
+        let tiles = self.tiles.as_ref()?;
+        let usable = self.neighbours.radius().is_some_and(|r| r <= tiles.tile_size);
+        if !usable || !tiles.fits(self.width(), self.height()) {
+            return Some(vec![(0, 0, self.width(), self.height())]);
+        }
+        let regions = tiles.active()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &active)| active)
+            .map(|(i, _)| tiles.region(i, self.width(), self.height()))
+            .collect();
+        Some(regions)
+    }
+
+    /// Enables or disables recording of statistics for each generation.
+    ///
+    /// When enabled the current generation is recorded immediately, followed by each
+    /// generation produced by `step_mut`. Disabling discards any recorded statistics.
+    ///
+    /// Worlds returned by `step` do not record statistics, so that stepping immutably
+    /// does not copy every recorded generation.
+    pub fn set_recording(&mut self, enabled: bool) {
+        self.stats = if enabled {
+            let mut recorder = StatsRecorder::new();
+            recorder.record(self.gen, 0, 0, &self.curr);
+            Some(recorder)
+        }
+        else {
+            None
+        };
+    }
+
+    /// Returns the recorded statistics, or `None` if recording is disabled
+    pub fn statistics(&self) -> Option<&StatsRecorder> {
+        self.stats.as_ref()
+    }
+
+    /// Enables recording of up to `depth` previous states, allowing the world to be
+    /// stepped backwards. A `depth` of `0` disables the history.
+    ///
+    /// Each step records the state before it. The first call to `grid_mut` at each
+    /// generation also records the state before any edits, so stepping back undoes the
+    /// edits. Stepping forward again after stepping back starts a new branch of history.
+    ///
+    /// Any previously recorded history is discarded. Worlds returned by `step` do not
+    /// record history.
+    pub fn set_history(&mut self, depth: usize, mode: HistoryMode) {
+        self.history = if depth > 0 { Some(History::new(depth, mode)) } else { None };
+    }
+
+    /// Returns the generation of each state which can be restored, oldest first
+    pub fn history(&self) -> Vec<i64> {
+        self.history.as_ref().map_or(Vec::new(), |history| history.generations(self.gen, &self.curr))
+    }
+
+    /// Restores the most recently recorded state, undoing the last step or the last
+    /// group of edits. Returns false if there is no state to restore.
+    pub fn step_back(&mut self) -> bool {
+        let gen = match self.history {
+            Some(ref mut history) => history.pop(self.gen, &mut self.curr),
+            None                  => None,
+        };
+        match gen {
+            Some(gen) => {
+                self.restored(gen);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Steps back to the most recently recorded state at generation `gen`.
+    ///
+    /// Returns false, leaving the world unchanged, if no state at that generation is
+    /// in the history.
+    pub fn rewind_to(&mut self, gen: i64) -> bool {
+        if gen == self.gen {
+            return true;
+        }
+        let gens = self.history();
+        match gens.iter().rposition(|&g| g == gen) {
+            Some(i) => {
+                for _ in i..gens.len() {
+                    self.step_back();
+                }
+                true
+            },
+            None => false,
+        }
     }
 
-    /// Sets the rules function
-    pub fn set_rules(&mut self, rules: RulesFn) {
-        self.rules = rules;
+    /// Updates the world after the state at generation `gen` was restored into `curr`
+    fn restored(&mut self, gen: i64) {
+        self.gen = gen;
+        self.reset_tiles();
+        if let Some(ref mut recorder) = self.stats {
+            recorder.truncate(gen);
+        }
     }
 
-    /// Sets the neighbours function
-    pub fn set_neighbours(&mut self, neighbours: NeighboursFn) {
-        self.neighbours = neighbours;
+    /// Forces every tile to be recomputed by the next step
+    fn reset_tiles(&mut self) {
+        if let Some(ref mut tiles) = self.tiles {
+            tiles.changed = None;
+        }
+    }
+
+    /// Returns the rule and edges to use for bit-packed stepping, if applicable
+    fn bit_packed_rule(&self) -> Option<(LifeLike, Edges)> {
+        if self.backend != Backend::BitPacked {
+            return None;
+        }
+        match (self.rules.life_like(), self.neighbours.moore_edges()) {
+            (Some(rule), Some(edges)) => Some((rule, edges)),
+            _                         => None,
+        }
+    }
+
+    /// Returns how the edges of the grid are treated, if the neighbourhood is the
+    /// Moore neighbourhood
+    pub fn edges(&self) -> Option<Edges> {
+        self.neighbours.moore_edges()
     }
 
     /// Gets the current generation for this `World`
@@ -58,41 +327,135 @@ impl World {
     /// Executes a single step of this `World` in place
     pub fn step_mut(&mut self) {
         use std::mem::swap;
-        let curr = &mut self.curr;
-        // Allocate prev?
-        if self.prev.is_none() {
-            self.prev = Some(curr.clone());
+        if let Some(ref mut history) = self.history {
+            history.settle(self.gen, &self.curr);
         }
-        let next = self.prev.as_mut().unwrap();
-        // Generate the next world state from the current
-        for (x, y, cell) in curr.iter_cells() {
-            let neighbours = (self.neighbours)(curr, x, y);
-            let new_cell = (self.rules)(cell, neighbours);
-            next.set_cell(x, y, new_cell);
+        if let Some((rule, edges)) = self.bit_packed_rule() {
+            let (curr, prev) = (&mut self.curr, &mut self.prev);
+            // Allocate (or reallocate, if the grid was replaced) the packed grids?
+            let fits = match self.packed {
+                Some((ref bits, _)) => bits.width() == curr.width() && bits.height() == curr.height(),
+                None                => false,
+            };
+            if !fits {
+                self.packed = Some((BitGrid::create_dead(curr.width(), curr.height()),
+                                    BitGrid::create_dead(curr.width(), curr.height())));
+            }
+            let &mut (ref mut bits, ref mut next) = self.packed.as_mut().unwrap();
+            bits.load(curr);
+            bits.step_into_parallel(&rule, edges, next, self.threads);
+            match self.history {
+                Some(ref mut history) => {
+                    // Store into the spare grid and swap, keeping the current state to record
+                    let prev = spare_grid(prev, curr);
+                    next.store(prev);
+                    swap(curr, prev);
+                    history.push(self.gen, prev, curr);
+                },
+                None => next.store(curr),
+            }
+            if let Some(ref mut recorder) = self.stats {
+                let (births, deaths) = bits.count_changes(next);
+                recorder.record(self.gen + 1, births, deaths, curr);
+            }
+            self.reset_tiles();
+            self.gen += 1;
+            return;
+        }
+        // Generate the next world state from the current. Tiles which are not active
+        // are stable, so prev already holds their next state.
+        let (w, h, radius) = (self.width(), self.height(), self.neighbours.radius());
+        let usable = self.tiles.as_mut().is_some_and(|tiles| tiles.prepare(radius, w, h));
+        let next = spare_grid(&mut self.prev, &self.curr);
+        let curr = &mut self.curr;
+        let (rules, neighbours, threads) = (&*self.rules, &*self.neighbours, self.threads);
+        match self.tiles {
+            Some(ref mut tiles) if usable => {
+                let active = tiles.active();
+                step_cells(curr, rules, neighbours, threads, Some((tiles, &active)), next);
+                tiles.record(&active, curr, next);
+            },
+            _ => step_cells(curr, rules, neighbours, threads, None, next),
         }
         // ...and swap the two values
         swap(curr, next);
         self.gen += 1;
+        if let Some(ref mut recorder) = self.stats {
+            let (births, deaths) = stats::count_changes(next, curr);
+            recorder.record(self.gen, births, deaths, curr);
+        }
+        if let Some(ref mut history) = self.history {
+            history.push(self.gen - 1, next, curr);
+        }
     }
 
     /// Executes a single step of this `World` and returns a new, modified world
+    ///
+    /// The new world does not record statistics or history, which are only recorded by
+    /// `step_mut`.
     pub fn step(&self) -> World {
+        let (w, h, radius) = (self.width(), self.height(), self.neighbours.radius());
+        let mut tiles = self.tiles.clone();
         // Generate the next world state from the current
-        let next =
-            self.curr
-                .iter_cells()
-                .map(|(x, y, cell)| {
-                    let neighbours = (self.neighbours)(&self.curr, x, y);
-                    (self.rules)(cell, neighbours)
-                })
-                .collect();
-
-        let next = Grid::from_raw(self.width(), self.height(), next);
+        let next = if let Some((rule, edges)) = self.bit_packed_rule() {
+            if let Some(ref mut tiles) = tiles {
+                tiles.changed = None;
+            }
+            let mut next = BitGrid::create_dead(w, h);
+            BitGrid::from_grid(&self.curr).step_into_parallel(&rule, edges, &mut next, self.threads);
+            next.to_grid()
+        }
+        else {
+            let usable = tiles.as_mut().is_some_and(|tiles| tiles.prepare(radius, w, h));
+            let (rules, neighbours, threads) = (&*self.rules, &*self.neighbours, self.threads);
+            match tiles {
+                Some(ref mut tiles) if usable => {
+                    // Tiles which are not active are stable, so start from the current state
+                    let mut next = self.curr.clone();
+                    let active = tiles.active();
+                    step_cells(&self.curr, rules, neighbours, threads, Some((tiles, &active)), &mut next);
+                    tiles.record(&active, &self.curr, &next);
+                    next
+                },
+                _ => {
+                    let mut next = Grid::create_dead(w, h);
+                    step_cells(&self.curr, rules, neighbours, threads, None, &mut next);
+                    next
+                },
+            }
+        };
+
         World { gen: self.gen + 1,
-                rules: self.rules,
-                neighbours: self.neighbours,
+                rules: self.rules.clone(),
+                neighbours: self.neighbours.clone(),
                 curr: next,
-                prev: None }
+                prev: None,
+                backend: self.backend,
+                packed: None,
+                threads: self.threads,
+                tiles,
+                stats: None,
+                history: None }
+    }
+
+    /// Returns a copy of this world at the same generation, which steps identically but
+    /// does not record statistics or history
+    pub(crate) fn fork(&self) -> World {
+        let mut tiles = self.tiles.clone();
+        if let Some(ref mut tiles) = tiles {
+            tiles.changed = None;
+        }
+        World { gen: self.gen,
+                rules: self.rules.clone(),
+                neighbours: self.neighbours.clone(),
+                curr: self.curr.clone(),
+                prev: None,
+                backend: self.backend,
+                packed: None,
+                threads: self.threads,
+                tiles,
+                stats: None,
+                history: None }
     }
 
     /// Get a reference to the current grid
@@ -101,11 +464,214 @@ impl World {
     }
 
     /// Get a mutable reference to the current grid
+    ///
+    /// Any active region tracking is reset, so the next step recomputes every cell.
+    /// If history is enabled the state before the first edit at this generation is recorded.
     pub fn grid_mut(&mut self) -> &mut Grid {
+        self.reset_tiles();
+        if let Some(ref mut history) = self.history {
+            history.begin_edit(&self.curr);
+        }
         &mut self.curr
     }
 }
 
+/// Tracks which square tiles of a `World` changed in the previous generation
+#[derive(Clone)]
+struct ActiveTiles {
+    tile_size: usize,
+    cols: usize,
+    rows: usize,
+    /// The tiles which changed in the previous generation, or `None` if unknown
+    changed: Option<Vec<bool>>,
+}
+
+impl ActiveTiles {
+    fn new(tile_size: usize, width: usize, height: usize) -> ActiveTiles {
+        ActiveTiles {
+            tile_size,
+            cols: width.div_ceil(tile_size),
+            rows: height.div_ceil(tile_size),
+            changed: None,
+        }
+    }
+
+    /// Returns true if tracking applies to a neighbourhood of `radius`, resizing the tiles
+    /// to cover a grid of `width` and `height` if necessary
+    fn prepare(&mut self, radius: Option<usize>, width: usize, height: usize) -> bool {
+        if radius.is_none_or(|r| r > self.tile_size) {
+            return false;
+        }
+        if !self.fits(width, height) {
+            *self = ActiveTiles::new(self.tile_size, width, height);
+        }
+        true
+    }
+
+    /// Returns true if these tiles cover a grid of `width` and `height`
+    fn fits(&self, width: usize, height: usize) -> bool {
+        self.cols == width.div_ceil(self.tile_size) && self.rows == height.div_ceil(self.tile_size)
+    }
+
+    /// Returns the index of the tile containing the cell at `(x, y)`
+    #[inline]
+    fn index(&self, x: usize, y: usize) -> usize {
+        (y / self.tile_size) * self.cols + x / self.tile_size
+    }
+
+    /// Returns the region `(x, y, width, height)` covered by the tile at `index`
+    fn region(&self, index: usize, width: usize, height: usize) -> (usize, usize, usize, usize) {
+        let (x, y) = ((index % self.cols) * self.tile_size, (index / self.cols) * self.tile_size);
+        (x, y, self.tile_size.min(width - x), self.tile_size.min(height - y))
+    }
+
+    /// Returns the spans `(start, end)` of the columns in row `y` which lie within
+    /// `active` tiles, joining the spans of adjacent tiles
+    fn spans(&self, active: &[bool], y: usize, width: usize) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for col in (0..self.cols).filter(|&col| active[self.index(col * self.tile_size, y)]) {
+            let (start, end) = (col * self.tile_size, ((col + 1) * self.tile_size).min(width));
+            match spans.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _                             => spans.push((start, end)),
+            }
+        }
+        spans
+    }
+
+    /// Returns the tiles which must be recomputed: every tile which changed, along with
+    /// the tiles adjacent to them (wrapping around the edges of the grid)
+    fn active(&self) -> Vec<bool> {
+        let changed = match self.changed {
+            Some(ref changed) => changed,
+            None              => return vec![true; self.cols * self.rows],
+        };
+        let mut active = vec![false; self.cols * self.rows];
+        for (i, _) in changed.iter().enumerate().filter(|&(_, &c)| c) {
+            let (col, row) = (i % self.cols, i / self.cols);
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    let c = (col + self.cols + dx - 1) % self.cols;
+                    let r = (row + self.rows + dy - 1) % self.rows;
+                    active[r * self.cols + c] = true;
+                }
+            }
+        }
+        active
+    }
+
+    /// Records which of the `active` tiles differ between `curr` and `next`
+    fn record(&mut self, active: &[bool], curr: &Grid, next: &Grid) {
+        let mut changed = vec![false; self.cols * self.rows];
+        for ((x, y, cell), (_, _, next_cell)) in curr.iter_cells().zip(next.iter_cells()) {
+            let i = self.index(x, y);
+            if active[i] && !changed[i] && cell != next_cell {
+                changed[i] = true;
+            }
+        }
+        self.changed = Some(changed);
+    }
+}
+
+/// Returns the grid in `prev`, first allocating (or reallocating, if the grid was
+/// replaced) a copy of `curr` if it does not match the dimensions of `curr`
+fn spare_grid<'a>(prev: &'a mut Option<Grid>, curr: &Grid) -> &'a mut Grid {
+    let fits = match *prev {
+        Some(ref prev) => prev.width() == curr.width() && prev.height() == curr.height(),
+        None           => false,
+    };
+    if !fits {
+        *prev = Some(curr.clone());
+    }
+    prev.as_mut().unwrap()
+}
+
+/// Fails if `rules` depend on the arrangement of neighbours, which is only known for
+/// the Moore neighbourhood, and `neighbours` is not the Moore neighbourhood
+fn check_arrangement(rules: &dyn Rule, neighbours: &dyn Neighbourhood) -> Result<(), RuleError> {
+    if rules.transitions().is_some() && rules.life_like().is_none() && neighbours.moore_edges().is_none() {
+        return Err(RuleError::NeedsMooreNeighbourhood);
+    }
+    Ok(())
+}
+
+/// How `step_band` finds the next state of each cell
+#[derive(Clone, Copy)]
+enum Lookup<'a> {
+    /// The rules' table of next states for each configuration, and the Moore edges
+    Table(&'a [bool; 512], Edges),
+    /// The neighbour counts of every cell, counted together
+    Counts(&'a [usize]),
+    /// Counts the neighbours of each cell one at a time
+    Count,
+}
+
+/// Generates the next state of `curr` into `next`, on up to `threads` threads.
+///
+/// If `active` tiles are given, cells outside of them are left untouched.
+fn step_cells(curr: &Grid,
+              rules: &dyn RowRule,
+              neighbours: &dyn RowNeighbourhood,
+              threads: usize,
+              active: Option<(&ActiveTiles, &[bool])>,
+              next: &mut Grid) {
+    let table = match (rules.transitions(), neighbours.moore_edges()) {
+        (Some(table), Some(edges)) => Some((table, edges)),
+        _                          => None,
+    };
+    let counts = if table.is_none() { neighbours.count_all(curr) } else { None };
+    let lookup = match (&table, &counts) {
+        (Some((table, edges)), _) => Lookup::Table(table, *edges),
+        (_, Some(counts))         => Lookup::Counts(counts),
+        _                         => Lookup::Count,
+    };
+    for_each_band(next.cells_mut(), curr.width(), curr.height(), threads, |first, band| {
+        step_band(curr, rules, neighbours, lookup, active, first, band);
+    });
+}
+
+/// Generates the next state of the rows of `curr` starting at row `first` into `band`
+fn step_band(curr: &Grid,
+             rules: &dyn RowRule,
+             neighbours: &dyn RowNeighbourhood,
+             lookup: Lookup,
+             active: Option<(&ActiveTiles, &[bool])>,
+             first: usize,
+             band: &mut [Cell]) {
+    let width = curr.width();
+    if width == 0 {
+        return;
+    }
+    let mut counts = vec![0; width];
+    for (row, next) in band.chunks_mut(width).enumerate() {
+        let y = first + row;
+        let cells = &curr.cells()[y * width..(y + 1) * width];
+        let spans = match active {
+            Some((tiles, active)) => tiles.spans(active, y, width),
+            None                  => vec![(0, width)],
+        };
+        for (start, end) in spans {
+            let (cells, next) = (&cells[start..end], &mut next[start..end]);
+            match lookup {
+                Lookup::Table(table, edges) => {
+                    for (x, new_cell) in (start..end).zip(next) {
+                        let live = table[rules::configuration(curr, x, y, edges)];
+                        *new_cell = if live { Cell::Live } else { Cell::Dead };
+                    }
+                },
+                Lookup::Counts(all) => {
+                    rules.next_row(cells, &all[y * width + start..y * width + end], next);
+                },
+                Lookup::Count => {
+                    let counts = &mut counts[start..end];
+                    neighbours.count_row(curr, start, y, counts);
+                    rules.next_row(cells, counts, next);
+                },
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -320,6 +886,347 @@ mod tests {
 
     }
 
+    #[test]
+    fn can_step_world_with_stateful_rules() {
+        use rules::{ Rule, Neighbourhood };
+        use grid::Cell;
+
+        //Counts only the cells directly above and below
+        struct Vertical { wrap: bool }
+        impl Neighbourhood for Vertical {
+            fn count(&self, grid: &Grid, x: usize, y: usize) -> usize {
+                let h = grid.height();
+                let above = if y > 0 { Some(y - 1) } else if self.wrap { Some(h - 1) } else { None };
+                let below = if y + 1 < h { Some(y + 1) } else if self.wrap { Some(0) } else { None };
+                above.into_iter().chain(below)
+                     .filter(|&y| grid.cell_at(x, y).is_live())
+                     .count()
+            }
+        }
+
+        //Cells are live when they have at least `min` neighbours
+        struct Threshold { min: usize }
+        impl Rule for Threshold {
+            fn next_cell(&self, _: &Cell, neighbours: usize) -> Option<Cell> {
+                Some(if neighbours >= self.min { Live } else { Dead })
+            }
+        }
+
+        let mut w = World::new(Grid::from_raw(1, 3, vec![Live, Dead, Live]));
+        w.set_rules(Threshold { min: 1 }).unwrap();
+        w.set_neighbours(Vertical { wrap: false }).unwrap();
+
+        let next = w.step();
+        assert_eq!(next.grid(), &Grid::from_raw(1, 3, vec![Dead, Live, Dead]));
+
+        w.set_neighbours(Vertical { wrap: true }).unwrap();
+        w.step_mut();
+        assert_eq!(w.grid(), &Grid::from_raw(1, 3, vec![Live, Live, Live]));
+    }
+
+    #[test]
+    fn can_step_world_with_parsed_rule() {
+        use std::str::FromStr;
+
+        use grid::Cell::Dead as X;
+        use grid::Cell::Live as O;
+
+        //Under Seeds (B2/S) every live cell dies and cells with two neighbours are born
+        let mut w = World::new(Grid::from_raw(4, 4, vec![
+            X, X, X, X,
+            X, O, O, X,
+            X, X, X, X,
+            X, X, X, X,
+        ]));
+        w.set_rules(rules::LifeLike::from_str("B2/S").unwrap()).unwrap();
+        w.set_neighbours(rules::terminal_neighbours).unwrap();
+
+        w.step_mut();
+
+        let expected = Grid::from_raw(4, 4, vec![
+            X, O, O, X,
+            X, X, X, X,
+            X, O, O, X,
+            X, X, X, X,
+        ]);
+
+        assert_eq!(&w.curr, &expected);
+    }
+
+    #[test]
+    fn can_step_world_on_another_thread() {
+        use std::thread;
+
+        let mut w = make_glider_world();
+        w.set_rules(rules::LifeLike::conway()).unwrap();
+        let w = thread::spawn(move || {
+            w.step_mut();
+            w
+        }).join().unwrap();
+
+        assert_eq!(w.grid(), make_glider_world().step().grid());
+    }
+
+    #[test]
+    fn bit_packed_backend_matches_cells_backend() {
+        use super::Backend;
+        use bitgrid::tests::make_random_grid;
+
+        let grid = make_random_grid(100, 70, 42);
+
+        for &terminal in &[false, true] {
+            let mut cells = World::new(grid.clone());
+            let mut packed = World::new(grid.clone());
+            packed.set_backend(Backend::BitPacked);
+            if terminal {
+                cells.set_neighbours(rules::TerminalNeighbours).unwrap();
+                packed.set_neighbours(rules::TerminalNeighbours).unwrap();
+            }
+
+            for _ in 0..10 {
+                cells.step_mut();
+                packed = packed.step();
+                packed.step_mut();
+                cells.step_mut();
+                assert_eq!(cells.grid(), packed.grid());
+                assert_eq!(cells.generation(), packed.generation());
+            }
+        }
+    }
+
+    #[test]
+    fn threaded_stepping_matches_serial_stepping() {
+        use super::Backend;
+        use bitgrid::tests::make_random_grid;
+
+        let grid = make_random_grid(67, 41, 9);
+
+        for &backend in &[Backend::Cells, Backend::BitPacked] {
+            for &terminal in &[false, true] {
+                for &threads in &[2, 3, 8, 100] {
+                    let mut serial = World::new(grid.clone());
+                    let mut threaded = World::new(grid.clone());
+                    serial.set_backend(backend);
+                    threaded.set_backend(backend);
+                    threaded.set_threads(threads);
+                    if terminal {
+                        serial.set_neighbours(rules::TerminalNeighbours).unwrap();
+                        threaded.set_neighbours(rules::TerminalNeighbours).unwrap();
+                    }
+
+                    for _ in 0..5 {
+                        serial.step_mut();
+                        threaded.step_mut();
+                        assert_eq!(serial.grid(), threaded.grid());
+                    }
+                    assert_eq!(serial.step().grid(), threaded.step().grid());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bit_packed_backend_falls_back_for_other_rules() {
+        use super::Backend;
+
+        let mut w = make_glider_world();
+        w.set_backend(Backend::BitPacked);
+        w.set_rules(|_: &::grid::Cell, _| Dead).unwrap();
+
+        w.step_mut();
+
+        assert_eq!(w.grid(), &Grid::create_dead(6, 5));
+    }
+
+    #[test]
+    fn tracked_stepping_matches_untracked_stepping() {
+        use bitgrid::tests::make_random_grid;
+
+        let grid = make_random_grid(37, 23, 4);
+
+        for &terminal in &[false, true] {
+            for &tile_size in &[1, 4, 16, 64] {
+                let mut plain = World::new(grid.clone());
+                let mut tracked = World::new(grid.clone());
+                tracked.set_active_tracking(Some(tile_size));
+                tracked.set_threads(3);
+                if terminal {
+                    plain.set_neighbours(rules::TerminalNeighbours).unwrap();
+                    tracked.set_neighbours(rules::TerminalNeighbours).unwrap();
+                }
+
+                for gen in 0..60 {
+                    plain.step_mut();
+                    tracked = if gen % 2 == 0 { tracked.step() } else { tracked.step_mut(); tracked };
+                    assert_eq!(plain.grid(), tracked.grid(), "tile size {} generation {}", tile_size, gen);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stable_regions_become_inactive() {
+        let mut grid = Grid::create_dead(16, 16);
+        grid.write_cells(1, 1, &Grid::from_raw(3, 1, vec![Live, Live, Live]));
+        grid.write_cells(10, 10, &Grid::from_raw(2, 2, vec![Live, Live, Live, Live]));
+        let mut w = World::new(grid);
+
+        assert_eq!(w.active_regions(), None);
+        w.set_active_tracking(Some(4));
+        assert_eq!(w.active_regions().unwrap().len(), 16);
+
+        // Only the blinker, at the top left, keeps changing
+        w.step_mut();
+        w.step_mut();
+        let mut regions = w.active_regions().unwrap();
+        regions.sort();
+        assert_eq!(regions, vec![(0, 0, 4, 4), (0, 4, 4, 4), (0, 12, 4, 4),
+                                 (4, 0, 4, 4), (4, 4, 4, 4), (4, 12, 4, 4),
+                                 (12, 0, 4, 4), (12, 4, 4, 4), (12, 12, 4, 4)]);
+
+        // Editing the grid makes every tile active again
+        w.grid_mut().set_cell(10, 10, Dead);
+        assert_eq!(w.active_regions().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn active_regions_are_clipped_to_grid() {
+        let mut w = World::new(Grid::create_dead(10, 5));
+        w.set_active_tracking(Some(8));
+
+        assert_eq!(w.active_regions(), Some(vec![(0, 0, 8, 5), (8, 0, 2, 5)]));
+
+        w.step_mut();
+        assert_eq!(w.active_regions(), Some(vec![]));
+    }
+
+    #[test]
+    fn can_step_back_through_history() {
+        use super::{ Backend, HistoryMode };
+
+        for &backend in &[Backend::Cells, Backend::BitPacked] {
+            for &mode in &[HistoryMode::Full, HistoryMode::Diffs] {
+                let mut w = make_glider_world();
+                w.set_backend(backend);
+                w.set_history(8, mode);
+
+                let mut states = vec![w.grid().clone()];
+                for _ in 0..10 {
+                    w.step_mut();
+                    states.push(w.grid().clone());
+                }
+                assert_eq!(w.history(), (2..10).collect::<Vec<_>>());
+
+                for gen in (2..10).rev() {
+                    assert!(w.step_back());
+                    assert_eq!(w.generation(), gen);
+                    assert_eq!(w.grid(), &states[gen as usize], "{:?} {:?}", backend, mode);
+                }
+                assert!(!w.step_back());
+                assert_eq!(w.generation(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn can_rewind_to_generation() {
+        use super::HistoryMode;
+
+        let mut w = make_glider_world();
+        w.set_recording(true);
+        w.set_history(3, HistoryMode::Diffs);
+        let expected = w.step().step().grid().clone();
+        for _ in 0..5 {
+            w.step_mut();
+        }
+
+        assert!(!w.rewind_to(1));
+        assert_eq!(w.generation(), 5);
+        assert!(w.rewind_to(2));
+        assert_eq!(w.generation(), 2);
+        assert_eq!(w.grid(), &expected);
+        assert_eq!(w.statistics().unwrap().entries().len(), 3);
+        assert!(w.history().is_empty());
+    }
+
+    #[test]
+    fn edits_are_recorded_as_branches() {
+        use super::HistoryMode;
+
+        let mut w = make_glider_world();
+        w.set_history(10, HistoryMode::Diffs);
+        w.step_mut();
+        let original = w.grid().clone();
+
+        // Edits made at the same generation are undone together
+        w.grid_mut().set_cell(0, 0, Live);
+        w.grid_mut().set_cell(5, 4, Live);
+        let edited = w.grid().clone();
+        assert_eq!(w.history(), vec![0, 1]);
+
+        w.step_mut();
+        w.step_mut();
+        assert_eq!(w.history(), vec![0, 1, 1, 2]);
+
+        assert!(w.rewind_to(1));
+        assert_eq!(w.grid(), &edited);
+        assert!(w.step_back());
+        assert_eq!(w.generation(), 1);
+        assert_eq!(w.grid(), &original);
+        assert!(w.rewind_to(0));
+        assert_eq!(w.grid(), &make_glider_world().grid().clone());
+    }
+
+    #[test]
+    fn unchanged_grid_mut_is_not_recorded() {
+        use super::HistoryMode;
+
+        let mut w = make_glider_world();
+        w.set_history(10, HistoryMode::Full);
+        w.step_mut();
+        let first = w.grid().clone();
+        w.step_mut();
+
+        w.grid_mut();
+        assert_eq!(w.history(), vec![0, 1]);
+        assert!(w.rewind_to(1));
+        assert_eq!(w.generation(), 1);
+        assert_eq!(w.grid(), &first);
+        assert!(w.step_back());
+        assert_eq!(w.generation(), 0);
+    }
+
+    #[test]
+    fn can_step_generations_rules() {
+        use grid::Cell::Dead as X;
+        use grid::Cell::Live as O;
+        use grid::Cell;
+        const D: Cell = Cell::Dying(2);
+
+        let mut w = World::new(Grid::from_raw(4, 3, vec![
+            X, X, X, X,
+            X, O, O, X,
+            X, X, X, X,
+        ]));
+        w.set_rules(rules::Generations::brians_brain()).unwrap();
+        w.set_neighbours(rules::terminal_neighbours).unwrap();
+
+        w.step_mut();
+        assert_eq!(w.grid(), &Grid::from_raw(4, 3, vec![
+            X, O, O, X,
+            X, D, D, X,
+            X, O, O, X,
+        ]));
+
+        // Dying cells are not counted as neighbours, and die after one generation
+        w.step_mut();
+        assert_eq!(w.grid(), &Grid::from_raw(4, 3, vec![
+            X, D, D, X,
+            O, X, X, O,
+            X, D, D, X,
+        ]));
+    }
+
     // Benchmarks
 
     use test::Bencher;
@@ -334,8 +1241,35 @@ mod tests {
         let grid = make_even_grid(500, 500);
         let mut world = World::new(grid);
 
-        world.set_rules(rules::standard_rules);
-        world.set_neighbours(rules::torus_neighbours);
+        world.set_rules(rules::standard_rules).unwrap();
+        world.set_neighbours(rules::torus_neighbours).unwrap();
+
+        b.iter(|| world.step_mut());
+    }
+
+    #[bench]
+    fn bench_bit_packed_standard_rules(b: &mut Bencher) {
+        use super::Backend;
+
+        let grid = make_even_grid(500, 500);
+        let mut world = World::new(grid);
+
+        world.set_rules(rules::StandardRules).unwrap();
+        world.set_neighbours(rules::TorusNeighbours).unwrap();
+        world.set_backend(Backend::BitPacked);
+
+        b.iter(|| world.step_mut());
+    }
+
+    #[bench]
+    fn bench_threaded_standard_rules(b: &mut Bencher) {
+
+        let grid = make_even_grid(500, 500);
+        let mut world = World::new(grid);
+
+        world.set_rules(rules::standard_rules).unwrap();
+        world.set_neighbours(rules::torus_neighbours).unwrap();
+        world.set_threads(4);
 
         b.iter(|| world.step_mut());
     }